@@ -0,0 +1,9 @@
+require("util").inspect.defaultOptions.depth = 5; // Increase AVA's printing depth
+
+module.exports = {
+  timeout: "300000",
+  files: ["src/**/*.ava.ts"],
+  failWithoutAssertions: false,
+  extensions: ["ts"],
+  require: ["ts-node/register"],
+};
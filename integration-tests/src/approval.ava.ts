@@ -0,0 +1,46 @@
+import { call, createSeries, mint, STORAGE_DEPOSIT, test, useSandbox } from "./utils";
+
+useSandbox();
+
+test("approval history lists approvals and revokes in order", async (t) => {
+    const { contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    const tokenId = await mint(creator, contract, 1, alice);
+
+    await call(alice, contract, "nft_approve", { token_id: tokenId, account_id: bob.accountId }, STORAGE_DEPOSIT);
+    await call(alice, contract, "nft_approve", { token_id: tokenId, account_id: creator.accountId }, STORAGE_DEPOSIT);
+    await call(alice, contract, "nft_revoke", { token_id: tokenId, account_id: bob.accountId }, "1");
+
+    const history: any[] = await contract.view("token_approval_history", { token_id: tokenId });
+    t.deepEqual(history.map((event) => [event.action, event.account_id]), [
+        ["approve", bob.accountId],
+        ["approve", creator.accountId],
+        ["revoke", bob.accountId],
+    ]);
+    t.is(history[1].approval_id, history[0].approval_id + 1);
+    t.deepEqual(await contract.view("token_approval_history", { token_id: "1:2" }), []);
+});
+
+test("transfers clear approvals unless one is retained", async (t) => {
+    const { root, contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    const cleared = await mint(creator, contract, 1, alice);
+    const retained = await mint(creator, contract, 1, alice);
+    for (const tokenId of [cleared, retained]) {
+        await call(alice, contract, "nft_approve", { token_id: tokenId, account_id: bob.accountId }, STORAGE_DEPOSIT);
+        await call(alice, contract, "nft_approve", { token_id: tokenId, account_id: creator.accountId }, STORAGE_DEPOSIT);
+    }
+
+    await call(alice, contract, "nft_transfer", { receiver_id: root.accountId, token_id: cleared }, "1");
+    t.deepEqual((await contract.view("nft_token", { token_id: cleared }) as any).approved_account_ids, {});
+
+    await call(alice, contract, "nft_transfer", { receiver_id: root.accountId, token_id: retained, retain_approval_for: bob.accountId }, "1");
+    const token: any = await contract.view("nft_token", { token_id: retained });
+    t.deepEqual(Object.keys(token.approved_account_ids), [bob.accountId]);
+    t.true(await contract.view("nft_is_approved", { token_id: retained, approved_account_id: bob.accountId }));
+    t.false(await contract.view("nft_is_approved", { token_id: retained, approved_account_id: creator.accountId }));
+
+    //the retained approval works for the new owner's token
+    await call(bob, contract, "nft_transfer", { receiver_id: bob.accountId, token_id: retained, approval_id: token.approved_account_ids[bob.accountId] }, "1");
+    t.is(await contract.view("token_owner", { token_id: retained }), bob.accountId);
+});
@@ -0,0 +1,125 @@
+import { NEAR } from "near-workspaces";
+import { balanceOf, call, callRaw, createSeries, eventsOf, makeOffer, mint, offerStorage, secondsFromNow, STORAGE_DEPOSIT, test, useSandbox, waitUntil } from "./utils";
+
+useSandbox();
+
+test("expired tokens can't be transferred and can be burned by anyone", async (t) => {
+    const { worker } = t.context;
+    const { contract, creator, alice, bob } = t.context.accounts;
+    const expiresAt = await secondsFromNow(worker, 10);
+    await createSeries(creator, contract, 1, { expires_at: expiresAt });
+    const tokenId = await mint(creator, contract, 1, alice);
+
+    await t.throwsAsync(call(bob, contract, "expire_token", { token_id: tokenId }), { message: /Token has not expired/ });
+
+    await waitUntil(worker, expiresAt);
+    await t.throwsAsync(call(alice, contract, "nft_transfer", { receiver_id: bob.accountId, token_id: tokenId }, "1"), { message: /Token has expired/ });
+
+    await call(bob, contract, "expire_token", { token_id: tokenId });
+    await t.throwsAsync(contract.view("nft_token", { token_id: tokenId }), { message: /no token/ });
+    t.is(await contract.view("nft_supply_for_owner", { account_id: alice.accountId }), 0);
+});
+
+test("nft_distribute sends each token to its recipient or nothing at all", async (t) => {
+    const { contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    for (let i = 0; i < 4; i++) {
+        await mint(creator, contract, 1, creator);
+    }
+
+    const result = await callRaw(creator, contract, "nft_distribute", { distributions: [["1:1", alice.accountId], ["1:2", bob.accountId], ["1:3", alice.accountId]] }, "1");
+    t.false(result.failed);
+    t.is(await contract.view("token_owner", { token_id: "1:1" }), alice.accountId);
+    t.is(await contract.view("token_owner", { token_id: "1:2" }), bob.accountId);
+    t.is(await contract.view("token_owner", { token_id: "1:3" }), alice.accountId);
+    t.deepEqual(eventsOf(result.logs, "nft_transfer")[0].data.map((entry: any) => entry.token_ids), [["1:1", "1:3"], ["1:2"]]);
+
+    //1:1 now belongs to alice, so the whole call reverts and 1:4 stays with the creator
+    const unowned = call(creator, contract, "nft_distribute", { distributions: [["1:4", bob.accountId], ["1:1", bob.accountId]] }, "1");
+    await t.throwsAsync(unowned, { message: /Token 1:1 is not owned by the caller/ });
+    t.is(await contract.view("token_owner", { token_id: "1:4" }), creator.accountId);
+});
+
+test("token_owner returns the owner or null", async (t) => {
+    const { contract, creator, alice } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    const tokenId = await mint(creator, contract, 1, alice);
+
+    //token_owner only reads the token record, unlike nft_token it skips rendering the metadata and approvals
+    t.is(await contract.view("token_owner", { token_id: tokenId }), alice.accountId);
+    t.is(await contract.view("token_owner", { token_id: "1:2" }), null);
+    t.is((await contract.view("nft_token", { token_id: tokenId }) as any).owner_id, alice.accountId);
+});
+
+test("mint and transfer events stop while events are turned off", async (t) => {
+    const { contract, owner, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    await call(owner, contract, "set_emit_events", { enabled: false });
+
+    const silentMint = await callRaw(creator, contract, "nft_mint", { id: 1, receiver_id: alice.accountId }, STORAGE_DEPOSIT);
+    const silentTransfer = await callRaw(alice, contract, "nft_transfer", { receiver_id: bob.accountId, token_id: "1:1" }, "1");
+    t.deepEqual(eventsOf(silentMint.logs), []);
+    t.deepEqual(eventsOf(silentTransfer.logs), []);
+
+    await call(owner, contract, "set_emit_events", { enabled: true });
+    const loudMint = await callRaw(creator, contract, "nft_mint", { id: 1, receiver_id: alice.accountId }, STORAGE_DEPOSIT);
+    const loudTransfer = await callRaw(alice, contract, "nft_transfer", { receiver_id: bob.accountId, token_id: "1:2" }, "1");
+    t.deepEqual(eventsOf(loudMint.logs, "nft_mint")[0].data[0].token_ids, ["1:2"]);
+    t.deepEqual(eventsOf(loudTransfer.logs, "nft_transfer")[0].data[0].token_ids, ["1:2"]);
+});
+
+test("token_event_summary records the mint and each transfer", async (t) => {
+    const { contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    const tokenId = await mint(creator, contract, 1, alice);
+    t.deepEqual((await contract.view("token_event_summary", { token_id: tokenId }) as any[]).map(([, type]) => type), ["mint"]);
+
+    await call(alice, contract, "nft_transfer", { receiver_id: bob.accountId, token_id: tokenId }, "1");
+    await call(bob, contract, "nft_transfer", { receiver_id: alice.accountId, token_id: tokenId }, "1");
+    const summary: [string, string][] = await contract.view("token_event_summary", { token_id: tokenId });
+    t.deepEqual(summary.map(([, type]) => type), ["mint", "transfer", "transfer"]);
+    t.true(BigInt(summary[0][0]) < BigInt(summary[2][0]));
+    t.deepEqual(await contract.view("token_event_summary", { token_id: "1:2" }), []);
+});
+
+test("nft_mint refunds whatever is attached beyond the storage it uses", async (t) => {
+    const { contract, creator, alice } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+
+    const deposit = BigInt(NEAR.parse("5 N").toString());
+    const storageBefore = (await contract.accountView()).storage_usage;
+    const balanceBefore = await balanceOf(creator);
+    const result = await callRaw(creator, contract, "nft_mint", { id: 1, receiver_id: alice.accountId }, deposit.toString());
+    t.false(result.failed);
+    const storageCost = BigInt((await contract.accountView()).storage_usage - storageBefore) * BigInt("10000000000000000000");
+
+    //whatever the minter didn't get back was either burnt as gas or kept for the storage
+    const burnt = [result.result.transaction_outcome, ...result.result.receipts_outcome].reduce((sum, outcome) => sum + BigInt(outcome.outcome.tokens_burnt), BigInt(0));
+    const refund = (await balanceOf(creator)) - balanceBefore + deposit + burnt;
+    t.is(refund, deposit - storageCost);
+});
+
+test("nft_burn refunds the caller the token's storage but not the offers' storage", async (t) => {
+    const { contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    for (let i = 0; i < 3; i++) {
+        await mint(creator, contract, 1, alice);
+    }
+    await makeOffer(bob, contract, "1:2", NEAR.parse("1 N").toString());
+
+    //what alice gets back from a burn, with the gas she paid for it added back in
+    const burnRefund = async (tokenId: string) => {
+        const before = await balanceOf(alice);
+        const result = await callRaw(alice, contract, "nft_burn", { token_id: tokenId }, "1");
+        t.false(result.failed);
+        const burnt = [result.result.transaction_outcome, ...result.result.receipts_outcome].reduce((sum, outcome) => sum + BigInt(outcome.outcome.tokens_burnt), BigInt(0));
+        return (await balanceOf(alice)) - before + burnt;
+    };
+
+    //bob's offer storage goes back to bob, so both burns give alice the same refund
+    const bobBefore = await balanceOf(bob);
+    const plain = await burnRefund("1:1");
+    const withOffer = await burnRefund("1:2");
+    t.is(withOffer, plain);
+    t.is((await balanceOf(bob)) - bobBefore, BigInt(NEAR.parse("1 N").toString()) + offerStorage(bob));
+});
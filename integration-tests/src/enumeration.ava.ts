@@ -0,0 +1,102 @@
+import { call, createSeries, mint, secondsFromNow, test, useSandbox, waitUntil } from "./utils";
+
+useSandbox();
+
+test("series_holders tallies the tokens of each owner", async (t) => {
+    const { contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    await mint(creator, contract, 1, alice);
+    await mint(creator, contract, 1, bob);
+    await mint(creator, contract, 1, alice);
+    await mint(creator, contract, 1, creator);
+    await mint(creator, contract, 1, alice);
+
+    t.deepEqual(await contract.view("series_holders", { id: 1 }), [
+        [alice.accountId, 3],
+        [bob.accountId, 1],
+        [creator.accountId, 1],
+    ]);
+    t.deepEqual(await contract.view("series_holders", { id: 1, from_index: "1", limit: 1 }), [[bob.accountId, 1]]);
+    t.deepEqual(await contract.view("series_holders", { id: 2 }), []);
+});
+
+test("series_configs returns each requested series in position", async (t) => {
+    const { contract, creator } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    await createSeries(creator, contract, 3, { tags: ["music"] });
+
+    const configs: any[] = await contract.view("series_configs", { ids: [3, 2, 1, 42] });
+    t.is(configs.length, 4);
+    t.is(configs[0].series_id, 3);
+    t.is(configs[0].metadata.title, "Series 3");
+    t.deepEqual(configs[0].tags, ["music"]);
+    t.is(configs[1], null);
+    t.is(configs[2].series_id, 1);
+    t.is(configs[2].owner_id, creator.accountId);
+    t.is(configs[3], null);
+
+    //the input length is capped
+    await t.throwsAsync(contract.view("series_configs", { ids: Array.from({ length: 101 }, (_, i) => i) }));
+});
+
+test("tokens_held_longer_than only returns tokens held past the duration", async (t) => {
+    const { worker } = t.context;
+    const { contract, creator, alice } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    const oldToken = await mint(creator, contract, 1, alice);
+    await waitUntil(worker, await secondsFromNow(worker, 10));
+    await mint(creator, contract, 1, alice);
+
+    //five seconds in nanoseconds
+    const duration = (BigInt(5) * BigInt(1000000000)).toString();
+    t.deepEqual(await contract.view("tokens_held_longer_than", { account_id: alice.accountId, duration }), [oldToken]);
+    t.deepEqual(await contract.view("tokens_held_longer_than", { account_id: creator.accountId, duration }), []);
+});
+
+test("tokens_since returns only the tokens minted after the passed in index", async (t) => {
+    const { contract, creator, alice } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    for (let i = 0; i < 2; i++) {
+        await mint(creator, contract, 1, alice);
+    }
+    const [firstBatch, resumeFrom]: any = await contract.view("tokens_since", {});
+    t.deepEqual(firstBatch.map((token: any) => token.token_id), ["1:1", "1:2"]);
+    t.is(resumeFrom, "2");
+
+    for (let i = 0; i < 3; i++) {
+        await mint(creator, contract, 1, alice);
+    }
+    const [secondBatch, next]: any = await contract.view("tokens_since", { since_log_index: resumeFrom });
+    t.deepEqual(secondBatch.map((token: any) => token.token_id), ["1:3", "1:4", "1:5"]);
+    t.is(next, "5");
+});
+
+test("series_by_tag follows the tags of each series", async (t) => {
+    const { contract, creator } = t.context.accounts;
+    await createSeries(creator, contract, 1, { tags: ["art"] });
+    await createSeries(creator, contract, 2);
+    await call(creator, contract, "set_series_tags", { id: 2, tags: ["art", "music"] });
+
+    const idsByTag = async (tag: string) => ((await contract.view("series_by_tag", { tag })) as any[]).map((series) => series.series_id);
+    t.deepEqual(await idsByTag("art"), [1, 2]);
+    t.deepEqual(await idsByTag("music"), [2]);
+
+    //removing a tag takes the series out of that tag's index only
+    await call(creator, contract, "set_series_tags", { id: 2, tags: ["music"] });
+    t.deepEqual(await idsByTag("art"), [1]);
+    t.deepEqual(await idsByTag("music"), [2]);
+    t.deepEqual(await idsByTag("video"), []);
+});
+
+test("owns_any_in_series checks ownership across several series", async (t) => {
+    const { contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    await createSeries(creator, contract, 2);
+    await createSeries(creator, contract, 3);
+    await mint(creator, contract, 2, alice);
+    await mint(creator, contract, 3, bob);
+
+    t.true(await contract.view("owns_any_in_series", { account_id: alice.accountId, ids: [1, 2, 42] }));
+    t.false(await contract.view("owns_any_in_series", { account_id: alice.accountId, ids: [1, 3] }));
+    t.false(await contract.view("owns_any_in_series", { account_id: creator.accountId, ids: [1, 2, 3] }));
+});
@@ -0,0 +1,43 @@
+import { NEAR } from "near-workspaces";
+import { call, callRaw, createSeries, test, useSandbox } from "./utils";
+
+useSandbox();
+
+/*
+    no fungible token contract is deployed, so alice plays the FT contract and calls ft_on_transfer herself, the
+    way a real FT contract would after ft_transfer_call. The forwarded ft_transfer to the series owner fails in a
+    separate receipt since alice has no contract, which doesn't undo the mint
+*/
+test("ft_on_transfer mints only for the right token and amount", async (t) => {
+    const { contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1, { ft_price: [alice.accountId, "100"] });
+    const msg = JSON.stringify({ series_id: 1 });
+
+    await t.throwsAsync(call(alice, contract, "ft_on_transfer", { sender_id: bob.accountId, amount: "99", msg }), { message: /Must transfer exactly 100 to mint/ });
+    await t.throwsAsync(call(bob, contract, "ft_on_transfer", { sender_id: bob.accountId, amount: "100", msg }), { message: /Wrong fungible token for this series/ });
+    t.is(await contract.view("nft_supply_for_owner", { account_id: bob.accountId }), 0);
+
+    const result = await callRaw(alice, contract, "ft_on_transfer", { sender_id: bob.accountId, amount: "100", msg });
+    t.is(result.parseResult(), "0");
+    const tokens: any[] = await contract.view("nft_tokens_for_owner", { account_id: bob.accountId });
+    t.deepEqual(tokens.map((token) => token.token_id), ["1:1"]);
+});
+
+test("register_for_ft schedules storage_deposit and tracks registrations that succeed", async (t) => {
+    const { root, contract, owner, alice } = t.context.accounts;
+    //a second copy of this contract stands in for the FT contract since it implements storage_deposit too
+    const ft = await root.createSubAccount("ft", { initialBalance: NEAR.parse("10 N").toString() });
+    await ft.deploy("../build/nft.wasm");
+    await call(ft, ft, "init", { owner_id: root.accountId });
+
+    const deposit = NEAR.parse("0.1 N").toString();
+    await t.throwsAsync(call(alice, contract, "register_for_ft", { ft_account_id: ft.accountId }, deposit));
+
+    t.true(await call(owner, contract, "register_for_ft", { ft_account_id: ft.accountId }, deposit));
+    t.true(await contract.view("is_registered_for_ft", { ft_account_id: ft.accountId }));
+    t.not(await ft.view("storage_balance_of", { account_id: contract.accountId }), null);
+
+    //alice has no contract, so the storage_deposit call fails and nothing is tracked
+    t.false(await call(owner, contract, "register_for_ft", { ft_account_id: alice.accountId }, deposit));
+    t.false(await contract.view("is_registered_for_ft", { ft_account_id: alice.accountId }));
+});
@@ -0,0 +1,64 @@
+import { NEAR } from "near-workspaces";
+import { createSeries, mint, STORAGE_DEPOSIT, test, useSandbox } from "./utils";
+
+useSandbox();
+
+//a lazy series of three editions that anyone can buy for 1 NEAR
+const PRICE = NEAR.parse("1 N").toString();
+const PURCHASE_DEPOSIT = (BigInt(PRICE) + BigInt(STORAGE_DEPOSIT)).toString();
+
+test("unminted editions read as owned by the creator and materialize on purchase", async (t) => {
+    const { contract, creator, alice } = t.context.accounts;
+    await createSeries(creator, contract, 1, { metadata: { title: "Series 1", copies: 3 }, lazy: true, price: PRICE });
+
+    t.is((await contract.view("nft_token", { token_id: "1:1" }) as any).owner_id, creator.accountId);
+    t.is(await contract.view("nft_supply_for_series", { id: 1 }), 0);
+
+    const tokenId = await mint(alice, contract, 1, alice, PURCHASE_DEPOSIT);
+    t.is(tokenId, "1:1");
+    t.is((await contract.view("nft_token", { token_id: "1:1" }) as any).owner_id, alice.accountId);
+    t.is((await contract.view("nft_token", { token_id: "1:2" }) as any).owner_id, creator.accountId);
+    t.is(await contract.view("nft_supply_for_series", { id: 1 }), 1);
+
+    //editions past the supply don't exist at all
+    await t.throwsAsync(contract.view("nft_token", { token_id: "1:4" }));
+});
+
+test("token_owner agrees with nft_token on unminted editions", async (t) => {
+    const { contract, creator, alice } = t.context.accounts;
+    await createSeries(creator, contract, 1, { metadata: { title: "Series 1", copies: 3 }, lazy: true, price: PRICE });
+    await mint(alice, contract, 1, alice, PURCHASE_DEPOSIT);
+
+    const owners = async (tokenId: string) => [await contract.view("token_owner", { token_id: tokenId }), ((await contract.view("nft_token", { token_id: tokenId })) as any).owner_id];
+    t.deepEqual(await owners("1:1"), [alice.accountId, alice.accountId]);
+    t.deepEqual(await owners("1:2"), [creator.accountId, creator.accountId]);
+    //editions past the supply don't exist for either
+    t.is(await contract.view("token_owner", { token_id: "1:4" }), null);
+});
+
+test("materializing an edition lowers lazy_available and flips is_materialized", async (t) => {
+    const { contract, creator, alice } = t.context.accounts;
+    await createSeries(creator, contract, 1, { metadata: { title: "Series 1", copies: 3 }, lazy: true, price: PRICE });
+
+    t.is(await contract.view("lazy_available", { id: 1 }), "3");
+    t.false(await contract.view("is_materialized", { id: 1, edition: 1 }));
+
+    await mint(alice, contract, 1, alice, PURCHASE_DEPOSIT);
+    t.is(await contract.view("lazy_available", { id: 1 }), "2");
+    t.true(await contract.view("is_materialized", { id: 1, edition: 1 }));
+    t.false(await contract.view("is_materialized", { id: 1, edition: 2 }));
+});
+
+test("next_claimable_editions advances as editions are claimed", async (t) => {
+    const { contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1, { metadata: { title: "Series 1", copies: 5 }, lazy: true, price: PRICE });
+    t.deepEqual(await contract.view("next_claimable_editions", { id: 1, n: 3 }), [1, 2, 3]);
+
+    await mint(alice, contract, 1, alice, PURCHASE_DEPOSIT);
+    await mint(bob, contract, 1, bob, PURCHASE_DEPOSIT);
+    t.deepEqual(await contract.view("next_claimable_editions", { id: 1, n: 3 }), [3, 4, 5]);
+
+    //the preview stops at the end of the supply
+    await mint(alice, contract, 1, alice, PURCHASE_DEPOSIT);
+    t.deepEqual(await contract.view("next_claimable_editions", { id: 1, n: 3 }), [4, 5]);
+});
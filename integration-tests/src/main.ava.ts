@@ -0,0 +1,29 @@
+import { test, useSandbox } from "./utils";
+
+useSandbox();
+
+test("init sets the owner and the default metadata", async (t) => {
+    const { contract, owner } = t.context.accounts;
+
+    const metadata: any = await contract.view("nft_metadata", {});
+    t.is(metadata.spec, "nft-1.0.0");
+    t.is(metadata.name, "NFT Tutorial Contract");
+
+    const config: any = await contract.view("contract_config", {});
+    t.is(config.owner_id, owner.accountId);
+    t.is(config.default_royalty, null);
+    t.is(config.pending_owner, null);
+    t.is(config.treasury_id, null);
+    t.false(config.paused);
+});
+
+test("supported_interfaces lists the standards the contract implements", async (t) => {
+    const { contract, alice } = t.context.accounts;
+
+    const interfaces: string[] = await contract.view("supported_interfaces", {});
+    t.deepEqual([...interfaces].sort(), ["nep145", "nep171", "nep177", "nep178", "nep181", "nep199", "nep297"]);
+
+    //spot check an entry point of the enumeration and storage management standards
+    t.is(await contract.view("nft_total_supply", {}), 0);
+    t.is(await contract.view("storage_balance_of", { account_id: alice.accountId }), null);
+});
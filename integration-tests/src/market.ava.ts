@@ -0,0 +1,269 @@
+import { ExecutionContext } from "ava";
+import { NEAR, NearAccount } from "near-workspaces";
+import { balanceOf, call, callRaw, Context, createSeries, eventsOf, makeOffer, mint, offerStorage, secondsFromNow, STORAGE_DEPOSIT, test, useSandbox, waitUntil } from "./utils";
+
+useSandbox();
+
+//mint a token of a series with a 10% creator royalty to alice and put it up for auction, returning its token ID
+//and end timestamp. Bids don't extend the auction unless an extension window is passed in
+async function startAuction(t: ExecutionContext<Context>, args: { [key: string]: any } = {}): Promise<[string, string]> {
+    const { worker } = t.context;
+    const { contract, creator, alice } = t.context.accounts;
+    await createSeries(creator, contract, 1, { royalty: { [creator.accountId]: 1000 } });
+    const tokenId = await mint(creator, contract, 1, alice);
+    const endAt = args.end_at != null ? args.end_at : await secondsFromNow(worker, 15);
+    await call(alice, contract, "start_auction", { token_id: tokenId, reserve: NEAR.parse("1 N").toString(), end_at: endAt, extension_window: "0", ...args }, "1");
+    return [tokenId, endAt];
+}
+
+test("a settled auction sends the token to the winner and splits the bid", async (t) => {
+    const { worker } = t.context;
+    const { root, contract, creator, alice, bob } = t.context.accounts;
+    const [tokenId, endAt] = await startAuction(t);
+    t.is(await contract.view("token_owner", { token_id: tokenId }), contract.accountId);
+
+    await t.throwsAsync(call(bob, contract, "place_bid", { token_id: tokenId }, NEAR.parse("0.5 N").toString()), { message: /Bid must be at least the reserve/ });
+    await call(bob, contract, "place_bid", { token_id: tokenId }, NEAR.parse("2 N").toString());
+    await t.throwsAsync(call(root, contract, "settle_auction", { token_id: tokenId }), { message: /Auction has not ended/ });
+
+    await waitUntil(worker, endAt);
+    const sellerBefore = await balanceOf(alice);
+    const creatorBefore = await balanceOf(creator);
+    const result = await callRaw(root, contract, "settle_auction", { token_id: tokenId });
+    t.is(eventsOf(result.logs, "auction_settle")[0].data[0].winner_id, bob.accountId);
+
+    t.is(await contract.view("token_owner", { token_id: tokenId }), bob.accountId);
+    t.is((await balanceOf(alice)) - sellerBefore, BigInt(NEAR.parse("1.8 N").toString()));
+    t.is((await balanceOf(creator)) - creatorBefore, BigInt(NEAR.parse("0.2 N").toString()));
+    t.is(await contract.view("token_auction", { token_id: tokenId }), null);
+});
+
+test("an auction without a bid at the reserve returns the token to the seller", async (t) => {
+    const { worker } = t.context;
+    const { root, contract, alice } = t.context.accounts;
+    const [tokenId, endAt] = await startAuction(t);
+
+    await waitUntil(worker, endAt);
+    const result = await callRaw(root, contract, "settle_auction", { token_id: tokenId });
+    const settle = eventsOf(result.logs, "auction_settle")[0].data[0];
+    t.is(settle.winner_id, null);
+    t.is(settle.amount, null);
+    t.is(await contract.view("token_owner", { token_id: tokenId }), alice.accountId);
+});
+
+test("an auction can be cancelled until the first bid", async (t) => {
+    const { contract, alice, bob } = t.context.accounts;
+    const [tokenId] = await startAuction(t);
+
+    await t.throwsAsync(call(bob, contract, "cancel_auction", { token_id: tokenId }, "1"), { message: /Only the seller can cancel the auction/ });
+    await call(alice, contract, "cancel_auction", { token_id: tokenId }, "1");
+    t.is(await contract.view("token_owner", { token_id: tokenId }), alice.accountId);
+    t.is(await contract.view("token_auction", { token_id: tokenId }), null);
+
+    //once there's a bid the auction has to run to its end
+    await call(alice, contract, "start_auction", { token_id: tokenId, reserve: NEAR.parse("1 N").toString(), end_at: await secondsFromNow(t.context.worker, 60) }, "1");
+    await call(bob, contract, "place_bid", { token_id: tokenId }, NEAR.parse("1 N").toString());
+    await t.throwsAsync(call(alice, contract, "cancel_auction", { token_id: tokenId }, "1"), { message: /Cannot cancel an auction that has bids/ });
+    t.is(await contract.view("token_owner", { token_id: tokenId }), contract.accountId);
+});
+
+test("bids need to beat the minimum increment and extend auctions near their end", async (t) => {
+    const { worker } = t.context;
+    const { contract, creator, bob } = t.context.accounts;
+    //a 10% increment and a 60 second window, on an auction that ends in 30 seconds
+    const window = (BigInt(60) * BigInt(1000000000)).toString();
+    const endAt = await secondsFromNow(worker, 30);
+    const [tokenId] = await startAuction(t, { end_at: endAt, min_bid_increment_bps: 1000, extension_window: window });
+
+    await call(bob, contract, "place_bid", { token_id: tokenId }, NEAR.parse("1 N").toString());
+    const extended: any = await contract.view("token_auction", { token_id: tokenId });
+    t.is(extended.end_at, (BigInt(endAt) + BigInt(window)).toString());
+
+    const tooLow = call(creator, contract, "place_bid", { token_id: tokenId }, NEAR.parse("1.05 N").toString());
+    await t.throwsAsync(tooLow, { message: new RegExp(`Bid must be at least ${NEAR.parse("1.1 N").toString()}`) });
+
+    //the auction now ends more than a window away, so this bid doesn't extend it again
+    await call(creator, contract, "place_bid", { token_id: tokenId }, NEAR.parse("1.1 N").toString());
+    const state: any = await contract.view("token_auction", { token_id: tokenId });
+    t.is(state.high_bidder, creator.accountId);
+    t.is(state.end_at, extended.end_at);
+});
+
+test("token_auction reflects bids and becomes settleable after the end", async (t) => {
+    const { worker } = t.context;
+    const { contract, alice, bob } = t.context.accounts;
+    const [tokenId, endAt] = await startAuction(t);
+
+    const started: any = await contract.view("token_auction", { token_id: tokenId });
+    t.is(started.seller_id, alice.accountId);
+    t.is(started.reserve, NEAR.parse("1 N").toString());
+    t.is(started.high_bid, null);
+    t.is(started.end_at, endAt);
+    t.false(started.settleable);
+
+    await call(bob, contract, "place_bid", { token_id: tokenId }, NEAR.parse("1.5 N").toString());
+    const bid: any = await contract.view("token_auction", { token_id: tokenId });
+    t.is(bid.high_bidder, bob.accountId);
+    t.is(bid.high_bid, NEAR.parse("1.5 N").toString());
+    t.false(bid.settleable);
+
+    await waitUntil(worker, endAt);
+    t.true((await contract.view("token_auction", { token_id: tokenId }) as any).settleable);
+});
+
+test("the market fee, royalty and seller split add up to the winning bid", async (t) => {
+    const { worker } = t.context;
+    const { root, contract, owner, creator, alice, bob } = t.context.accounts;
+    await call(owner, contract, "set_market_fee", { account_id: bob.accountId, bps: 250 });
+    const [tokenId, endAt] = await startAuction(t);
+
+    //an amount that doesn't divide evenly so the rounding dust has to end up somewhere
+    const bid = BigInt(NEAR.parse("1 N").toString()) + BigInt(7);
+    await call(bob, contract, "place_bid", { token_id: tokenId }, bid.toString());
+    await waitUntil(worker, endAt);
+
+    //bob already paid the bid, so from here on his balance only moves by the fee
+    const accounts = [alice, creator, bob];
+    const before = await Promise.all(accounts.map(balanceOf));
+    await call(root, contract, "settle_auction", { token_id: tokenId });
+    const after = await Promise.all(accounts.map(balanceOf));
+    const [seller, royalty, fee] = after.map((balance, i) => balance - before[i]);
+
+    t.is(fee, (bid * BigInt(250)) / BigInt(10000));
+    t.is(royalty, ((bid - fee) * BigInt(1000)) / BigInt(10000));
+    t.is(seller + royalty + fee, bid);
+});
+
+test("expired tokens can't be burned while they're held in an auction", async (t) => {
+    const { worker } = t.context;
+    const { root, contract, creator, alice, bob } = t.context.accounts;
+    const expiresAt = await secondsFromNow(worker, 15);
+    await createSeries(creator, contract, 1, { expires_at: expiresAt });
+    const tokenId = await mint(creator, contract, 1, alice);
+    const endAt = await secondsFromNow(worker, 25);
+    await call(alice, contract, "start_auction", { token_id: tokenId, reserve: NEAR.parse("1 N").toString(), end_at: endAt, extension_window: "0" }, "1");
+    await call(bob, contract, "place_bid", { token_id: tokenId }, NEAR.parse("1 N").toString());
+
+    await waitUntil(worker, expiresAt);
+    await t.throwsAsync(call(root, contract, "expire_token", { token_id: tokenId }), { message: /Token is being auctioned/ });
+
+    //settling refunds the bid and gives the token back, after which it expires like any other
+    await waitUntil(worker, endAt);
+    await call(root, contract, "settle_auction", { token_id: tokenId });
+    t.is(await contract.view("token_owner", { token_id: tokenId }), alice.accountId);
+    await call(root, contract, "expire_token", { token_id: tokenId });
+    t.is(await contract.view("token_owner", { token_id: tokenId }), null);
+});
+
+test("offers can be made, accepted with a payout and withdrawn", async (t) => {
+    const { contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1, { royalty: { [creator.accountId]: 1000 } });
+    const tokenId = await mint(creator, contract, 1, alice);
+
+    const offer = BigInt(NEAR.parse("1 N").toString());
+    await t.throwsAsync(call(bob, contract, "make_offer", { token_id: tokenId }, (offerStorage(bob) + BigInt(1)).toString()), { message: /Offers need to be at least/ });
+    await makeOffer(bob, contract, tokenId, offer.toString());
+    await makeOffer(creator, contract, tokenId, offer.toString());
+    t.deepEqual(await contract.view("token_offers", { token_id: tokenId }), [
+        { buyer_id: bob.accountId, amount: offer.toString() },
+        { buyer_id: creator.accountId, amount: offer.toString() },
+    ]);
+
+    //withdrawing gives back the offer and its storage (minus the gas of the withdrawal)
+    const creatorBefore = await balanceOf(creator);
+    const withdrawal = await callRaw(creator, contract, "withdraw_offer", { token_id: tokenId }, "1");
+    t.is(eventsOf(withdrawal.logs, "offer_withdraw")[0].data[0].amount, offer.toString());
+    t.true((await balanceOf(creator)) > creatorBefore + BigInt(NEAR.parse("0.99 N").toString()));
+
+    //accepting transfers the token and pays the seller what's left after the royalty
+    const sellerBefore = await balanceOf(alice);
+    const buyerBefore = await balanceOf(bob);
+    await call(alice, contract, "accept_offer", { token_id: tokenId, buyer_id: bob.accountId }, "1");
+    t.is(await contract.view("token_owner", { token_id: tokenId }), bob.accountId);
+    t.true((await balanceOf(alice)) > sellerBefore + BigInt(NEAR.parse("0.89 N").toString()));
+    t.is((await balanceOf(bob)) - buyerBefore, offerStorage(bob));
+    t.deepEqual(await contract.view("token_offers", { token_id: tokenId }), []);
+});
+
+test("an approved account can't accept an offer for the owner", async (t) => {
+    const { root, contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    const tokenId = await mint(creator, contract, 1, alice);
+    await call(alice, contract, "nft_approve", { token_id: tokenId, account_id: root.accountId }, STORAGE_DEPOSIT);
+    await makeOffer(bob, contract, tokenId, NEAR.parse("1 N").toString());
+
+    const accept = call(root, contract, "accept_offer", { token_id: tokenId, buyer_id: bob.accountId }, "1");
+    await t.throwsAsync(accept, { message: /Only the token owner can accept offers/ });
+    t.is(await contract.view("token_owner", { token_id: tokenId }), alice.accountId);
+    t.is((await contract.view("token_offers", { token_id: tokenId }) as any[]).length, 1);
+});
+
+test("token_offers returns offers highest first", async (t) => {
+    const { root, contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    const tokenId = await mint(creator, contract, 1, alice);
+
+    const offers: [NearAccount, string][] = [[bob, "1 N"], [creator, "3 N"], [root, "2 N"]];
+    for (const [buyer, amount] of offers) {
+        await makeOffer(buyer, contract, tokenId, NEAR.parse(amount).toString());
+    }
+
+    const sorted: any[] = await contract.view("token_offers", { token_id: tokenId });
+    t.deepEqual(sorted.map((offer) => offer.buyer_id), [creator.accountId, root.accountId, bob.accountId]);
+    t.deepEqual(sorted.map((offer) => offer.amount), ["3 N", "2 N", "1 N"].map((amount) => NEAR.parse(amount).toString()));
+});
+
+test("offers at the auto accept price execute right away, lower ones queue", async (t) => {
+    const { contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    const tokenId = await mint(creator, contract, 1, alice);
+    const price = BigInt(NEAR.parse("2 N").toString());
+    await call(alice, contract, "set_auto_accept_price", { token_id: tokenId, price: price.toString() }, "1");
+
+    await makeOffer(bob, contract, tokenId, NEAR.parse("1 N").toString());
+    t.is(await contract.view("token_owner", { token_id: tokenId }), alice.accountId);
+    t.is((await contract.view("token_offers", { token_id: tokenId }) as any[]).length, 1);
+
+    //the new offer replaces bob's queued one and is executed since it's at the price
+    const result = await callRaw(bob, contract, "make_offer", { token_id: tokenId }, price.toString());
+    t.is(eventsOf(result.logs, "offer_accept")[0].data[0].amount, price.toString());
+    t.is(await contract.view("token_owner", { token_id: tokenId }), bob.accountId);
+    t.deepEqual(await contract.view("token_offers", { token_id: tokenId }), []);
+});
+
+test("token_state matches the dedicated views", async (t) => {
+    const { contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    const tokenId = await mint(creator, contract, 1, alice);
+    //there are no fixed-price listings, so the token is put up for sale through an auto accept price
+    await call(alice, contract, "set_auto_accept_price", { token_id: tokenId, price: NEAR.parse("5 N").toString() }, "1");
+    await makeOffer(bob, contract, tokenId, NEAR.parse("1 N").toString());
+
+    const state: any = await contract.view("token_state", { token_id: tokenId });
+    t.deepEqual(state.token, await contract.view("nft_token", { token_id: tokenId }));
+    t.deepEqual(state.offers, await contract.view("token_offers", { token_id: tokenId }));
+    t.is(state.auction, await contract.view("token_auction", { token_id: tokenId }));
+    t.is(state.auto_accept_price, NEAR.parse("5 N").toString());
+    t.true(state.transferable);
+    t.false(state.expired);
+    t.false(state.enforce_royalty_on_transfer);
+    t.is(await contract.view("token_state", { token_id: "1:2" }), null);
+});
+
+test("a series can turn the built-in marketplace off without blocking external sales", async (t) => {
+    const { worker } = t.context;
+    const { root, contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    const tokenId = await mint(creator, contract, 1, alice);
+    await t.throwsAsync(call(alice, contract, "set_builtin_market_enabled", { id: 1, enabled: false }));
+    await call(creator, contract, "set_builtin_market_enabled", { id: 1, enabled: false });
+
+    const auction = { token_id: tokenId, reserve: NEAR.parse("1 N").toString(), end_at: await secondsFromNow(worker, 60) };
+    await t.throwsAsync(call(alice, contract, "start_auction", auction, "1"), { message: /The built-in marketplace is disabled for this series/ });
+    await t.throwsAsync(makeOffer(bob, contract, tokenId, NEAR.parse("1 N").toString()), { message: /The built-in marketplace is disabled for this series/ });
+
+    //an external marketplace (bob here) can still be approved and move the token
+    await call(alice, contract, "nft_approve", { token_id: tokenId, account_id: bob.accountId }, STORAGE_DEPOSIT);
+    await call(bob, contract, "nft_transfer", { receiver_id: root.accountId, token_id: tokenId, approval_id: 0 }, "1");
+    t.is(await contract.view("token_owner", { token_id: tokenId }), root.accountId);
+});
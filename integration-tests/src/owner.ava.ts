@@ -0,0 +1,115 @@
+import { call, callRaw, createSeries, eventsOf, mint, STORAGE_DEPOSIT, test, useSandbox } from "./utils";
+
+useSandbox();
+
+test("contract_config reflects the owner's settings", async (t) => {
+    const { contract, owner, alice, bob } = t.context.accounts;
+
+    await call(owner, contract, "set_market_fee", { account_id: alice.accountId, bps: 250 });
+    await call(owner, contract, "set_platform_royalty", { account_id: bob.accountId, bps: 100 });
+    await call(owner, contract, "set_accumulate_royalties", { enabled: true });
+    await call(owner, contract, "set_max_royalty_bps", { bps: 2000 });
+    await call(owner, contract, "set_default_royalty", { royalty: { [alice.accountId]: 500 } });
+
+    const config: any = await contract.view("contract_config", {});
+    t.is(config.market_fee_recipient, alice.accountId);
+    t.is(config.market_fee_bps, 250);
+    t.is(config.platform_royalty_account, bob.accountId);
+    t.is(config.platform_royalty_bps, 100);
+    t.true(config.accumulate_royalties);
+    t.is(config.max_royalty_bps, 2000);
+    t.deepEqual(config.default_royalty, { [alice.accountId]: 500 });
+
+    //clearing the default royalty reports it as missing again
+    await call(owner, contract, "set_default_royalty", { royalty: null });
+    t.is((await contract.view("contract_config", {}) as any).default_royalty, null);
+});
+
+test("admin log records owner actions and paginates", async (t) => {
+    const { contract, owner, creator, alice } = t.context.accounts;
+
+    //the setup already logged adding the creator as an approved minter
+    await call(owner, contract, "add_approved_creator", { account_id: alice.accountId });
+    await call(owner, contract, "remove_approved_creator", { account_id: alice.accountId });
+    await call(owner, contract, "remove_approved_minters", { account_id: creator.accountId });
+
+    const log: any[] = await contract.view("get_admin_log", {});
+    t.is(log.length, 4);
+    t.true(log.every((action) => action.actor == owner.accountId));
+    t.is(log[0].description, `Added approved minter ${creator.accountId}`);
+    t.is(log[1].description, `Added approved creator ${alice.accountId}`);
+    t.is(log[2].description, `Removed approved creator ${alice.accountId}`);
+    t.is(log[3].description, `Removed approved minter ${creator.accountId}`);
+
+    const page: any[] = await contract.view("get_admin_log", { from_index: "2", limit: 1 });
+    t.deepEqual(page, [log[2]]);
+    t.deepEqual(await contract.view("get_admin_log", { from_index: "4" }), []);
+});
+
+test("pausing blocks state changes but keeps views working", async (t) => {
+    const { contract, owner, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    const tokenId = await mint(creator, contract, 1, alice);
+
+    await t.throwsAsync(call(alice, contract, "set_paused", { paused: true }));
+    const pause = await callRaw(owner, contract, "set_paused", { paused: true });
+    t.is(eventsOf(pause.logs, "contract_pause").length, 1);
+
+    const paused = { message: /Contract is paused/ };
+    await t.throwsAsync(mint(creator, contract, 1, alice), paused);
+    await t.throwsAsync(createSeries(creator, contract, 2), paused);
+    await t.throwsAsync(call(alice, contract, "nft_transfer", { receiver_id: bob.accountId, token_id: tokenId }, "1"), paused);
+    await t.throwsAsync(call(alice, contract, "nft_approve", { token_id: tokenId, account_id: bob.accountId }, STORAGE_DEPOSIT), paused);
+    t.is(await contract.view("token_owner", { token_id: tokenId }), alice.accountId);
+    t.true((await contract.view("contract_config", {}) as any).paused);
+
+    const unpause = await callRaw(owner, contract, "set_paused", { paused: false });
+    t.is(eventsOf(unpause.logs, "contract_unpause").length, 1);
+    await call(alice, contract, "nft_transfer", { receiver_id: bob.accountId, token_id: tokenId }, "1");
+    t.is(await contract.view("token_owner", { token_id: tokenId }), bob.accountId);
+});
+
+test("migrate fills in the fields an older state layout is missing", async (t) => {
+    const { contract, owner, creator, alice } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    await mint(creator, contract, 1, alice);
+    await mint(creator, contract, 1, alice);
+
+    //rewrite the state the way an older deployment stored it: without the fields added since and with null settings
+    const state = (await contract.viewState()).get("STATE");
+    ["paused", "emitEvents", "rolesEnumerable", "approvedMintersList", "approvedCreatorsList", "storageBalances", "stateVersion", "totalSupply", "totalBurned", "maxRoyaltyBps"].forEach((field) => delete state[field]);
+    Object.assign(state, { defaultRoyalty: null, pendingOwner: null, treasuryId: null });
+    await contract.patchState("STATE", JSON.stringify(state));
+    await t.throwsAsync(contract.view("contract_config", {}));
+
+    await t.throwsAsync(call(owner, contract, "migrate", {}), { message: /Only the contract itself can call this method/ });
+    t.is(await call(contract, contract, "migrate", {}), 1);
+
+    const config: any = await contract.view("contract_config", {});
+    t.false(config.paused);
+    t.is(config.max_royalty_bps, 10000);
+    t.is(config.default_royalty, null);
+    t.is(config.pending_owner, null);
+    t.is(config.treasury_id, null);
+    //the supply counter picks up the two tokens minted before the migration
+    t.is(await contract.view("nft_total_supply", {}), 2);
+    t.is(await contract.view("token_owner", { token_id: "1:2" }), alice.accountId);
+
+    const log: any[] = await contract.view("get_admin_log", {});
+    t.regex(log[log.length - 1].description, /^Migrated state from version 0 to 1, filled in: .*paused/);
+
+    //running it again changes nothing, and the migrated contract keeps working
+    await call(contract, contract, "migrate", {});
+    const rerun: any[] = await contract.view("get_admin_log", {});
+    t.is(rerun[rerun.length - 1].description, "Migrated state from version 1 to 1, filled in: nothing");
+    await mint(creator, contract, 1, alice);
+    t.is(await contract.view("nft_total_supply", {}), 3);
+
+    //the roster lists only count as complete once the owner has carried over the last page
+    const rolesEnumerable = async () => ((await contract.view("contract_config", {})) as any).roles_enumerable;
+    t.false(await rolesEnumerable());
+    await call(owner, contract, "migrate_approved_accounts", { minters: [creator.accountId], creators: [] });
+    t.false(await rolesEnumerable());
+    await call(owner, contract, "migrate_approved_accounts", { minters: [], creators: [], complete: true });
+    t.true(await rolesEnumerable());
+});
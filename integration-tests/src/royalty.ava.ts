@@ -0,0 +1,127 @@
+import { NEAR } from "near-workspaces";
+import { balanceOf, call, createSeries, makeOffer, mint, test, useSandbox } from "./utils";
+
+useSandbox();
+
+test("platform royalty is added to new series unless they opt out", async (t) => {
+    const { contract, owner, creator, bob } = t.context.accounts;
+    await call(owner, contract, "set_platform_royalty", { account_id: bob.accountId, bps: 250 });
+
+    await createSeries(creator, contract, 1, { royalty: { [creator.accountId]: 1000 } });
+    await createSeries(creator, contract, 2, { royalty: { [creator.accountId]: 1000 }, opt_out_platform_royalty: true });
+
+    const withPlatform: any = await contract.view("get_series_info", { id: 1 });
+    t.deepEqual(withPlatform.royalty, { [creator.accountId]: 1000, [bob.accountId]: 250 });
+
+    const optedOut: any = await contract.view("get_series_info", { id: 2 });
+    t.deepEqual(optedOut.royalty, { [creator.accountId]: 1000 });
+});
+
+test("series enforcing royalties only move through nft_transfer_payout", async (t) => {
+    const { contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1, { royalty: { [creator.accountId]: 1000 } });
+    await call(creator, contract, "set_enforce_royalty_on_transfer", { id: 1, enforce: true });
+    const tokenId = await mint(creator, contract, 1, alice);
+
+    const plain = call(alice, contract, "nft_transfer", { receiver_id: bob.accountId, token_id: tokenId }, "1");
+    await t.throwsAsync(plain, { message: /Series enforces royalties, use nft_transfer_payout/ });
+
+    const result: any = await call(alice, contract, "nft_transfer_payout", { receiver_id: bob.accountId, token_id: tokenId, balance: "1000", max_len_payout: 10 }, "1");
+    t.deepEqual(result.payout, { [creator.accountId]: "100", [alice.accountId]: "900" });
+    t.is(await contract.view("token_owner", { token_id: tokenId }), bob.accountId);
+});
+
+test("accumulated royalties are paid out in a single claim", async (t) => {
+    const { contract, owner, creator, alice, bob } = t.context.accounts;
+    await call(owner, contract, "set_accumulate_royalties", { enabled: true });
+    await createSeries(creator, contract, 1, { royalty: { [creator.accountId]: 1000 } });
+    const first = await mint(creator, contract, 1, alice);
+    const second = await mint(creator, contract, 1, alice);
+
+    //two sales credit the creator instead of paying them out
+    const creatorBefore = await balanceOf(creator);
+    for (const tokenId of [first, second]) {
+        await makeOffer(bob, contract, tokenId, NEAR.parse("1 N").toString());
+        await call(alice, contract, "accept_offer", { token_id: tokenId, buyer_id: bob.accountId }, "1");
+    }
+    t.is(await balanceOf(creator), creatorBefore);
+
+    const owed = NEAR.parse("0.2 N").toString();
+    t.is(await call(creator, contract, "claim_royalties", {}), owed);
+    t.true((await balanceOf(creator)) > creatorBefore + BigInt(NEAR.parse("0.19 N").toString()));
+    await t.throwsAsync(call(creator, contract, "claim_royalties", {}), { message: /No royalties to claim/ });
+});
+
+test("royalties_owed shows the unclaimed balance until it's claimed", async (t) => {
+    const { root, contract, owner, creator, alice, bob } = t.context.accounts;
+    await call(owner, contract, "set_accumulate_royalties", { enabled: true });
+    await createSeries(creator, contract, 1, { royalty: { [creator.accountId]: 500, [root.accountId]: 250 } });
+    const tokenId = await mint(creator, contract, 1, alice);
+    t.is(await contract.view("royalties_owed", { account_id: creator.accountId }), "0");
+
+    await makeOffer(bob, contract, tokenId, NEAR.parse("2 N").toString());
+    await call(alice, contract, "accept_offer", { token_id: tokenId, buyer_id: bob.accountId }, "1");
+    t.is(await contract.view("royalties_owed", { account_id: creator.accountId }), NEAR.parse("0.1 N").toString());
+    t.is(await contract.view("royalties_owed", { account_id: root.accountId }), NEAR.parse("0.05 N").toString());
+
+    await call(creator, contract, "claim_royalties", {});
+    t.is(await contract.view("royalties_owed", { account_id: creator.accountId }), "0");
+    t.is(await contract.view("royalties_owed", { account_id: root.accountId }), NEAR.parse("0.05 N").toString());
+});
+
+test("reassign_creator_royalty moves a creator's share in every series page by page", async (t) => {
+    const { contract, owner, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1, { royalty: { [creator.accountId]: 1000 } });
+    await createSeries(creator, contract, 2, { royalty: { [creator.accountId]: 1000, [alice.accountId]: 500 } });
+    await createSeries(creator, contract, 3, { royalty: { [alice.accountId]: 500 } });
+    await createSeries(creator, contract, 4, { royalty: { [creator.accountId]: 700, [bob.accountId]: 300 } });
+    await createSeries(creator, contract, 5, { royalty: { [creator.accountId]: 1000 } });
+    await call(creator, contract, "lock_series", { id: 5, freeze_royalty: true });
+
+    const args = { old_account_id: creator.accountId, new_account_id: bob.accountId, limit: 2 };
+    t.is(await call(owner, contract, "reassign_creator_royalty", { ...args, from_index: "0" }), 2);
+    t.is(await call(owner, contract, "reassign_creator_royalty", { ...args, from_index: "2" }), 1);
+    //the last page only holds the frozen series, which is left alone
+    t.is(await call(owner, contract, "reassign_creator_royalty", { ...args, from_index: "4" }), 0);
+
+    const royaltyOf = async (id: number) => (await contract.view("get_series_info", { id }) as any).royalty;
+    t.deepEqual(await royaltyOf(1), { [bob.accountId]: 1000 });
+    t.deepEqual(await royaltyOf(2), { [alice.accountId]: 500, [bob.accountId]: 1000 });
+    t.deepEqual(await royaltyOf(3), { [alice.accountId]: 500 });
+    //bob's existing share is folded together with the reassigned one
+    t.deepEqual(await royaltyOf(4), { [bob.accountId]: 1000 });
+    t.deepEqual(await royaltyOf(5), { [creator.accountId]: 1000 });
+});
+
+test("lowering the royalty cap scales down stored royalties in payouts", async (t) => {
+    const { contract, owner, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1, { royalty: { [creator.accountId]: 2000, [alice.accountId]: 1000 } });
+    const tokenId = await mint(creator, contract, 1, bob);
+
+    const payoutOf = async () => ((await contract.view("nft_payout", { token_id: tokenId, balance: "10000", max_len_payout: 10 })) as any).payout;
+    t.deepEqual(await payoutOf(), { [creator.accountId]: "2000", [alice.accountId]: "1000", [bob.accountId]: "7000" });
+
+    //the 30% royalty is clamped to 15% while keeping the 2:1 split
+    await call(owner, contract, "set_max_royalty_bps", { bps: 1500 });
+    t.deepEqual(await payoutOf(), { [creator.accountId]: "1000", [alice.accountId]: "500", [bob.accountId]: "8500" });
+    //the stored royalty itself is left as it was
+    t.deepEqual((await contract.view("get_series_info", { id: 1 }) as any).royalty, { [creator.accountId]: 2000, [alice.accountId]: 1000 });
+});
+
+test("nft_payout rejects a max_len_payout below the number of receivers", async (t) => {
+    const { root, contract, owner, creator, alice, bob } = t.context.accounts;
+    //four royalty recipients plus bob as the owner make five receivers
+    const royalty = { [root.accountId]: 100, [owner.accountId]: 100, [creator.accountId]: 100, [alice.accountId]: 100 };
+    await createSeries(creator, contract, 1, { royalty });
+    const tokenId = await mint(creator, contract, 1, bob);
+
+    const atLimit: any = await contract.view("nft_payout", { token_id: tokenId, balance: "10000", max_len_payout: 5 });
+    t.is(Object.keys(atLimit.payout).length, 5);
+    t.is(atLimit.payout[bob.accountId], "9600");
+
+    const overLimit = contract.view("nft_payout", { token_id: tokenId, balance: "10000", max_len_payout: 4 });
+    await t.throwsAsync(overLimit, { message: /Market cannot payout to that many receivers/ });
+    const transfer = call(bob, contract, "nft_transfer_payout", { receiver_id: alice.accountId, token_id: tokenId, balance: "10000", max_len_payout: 4 }, "1");
+    await t.throwsAsync(transfer, { message: /Market cannot payout to that many receivers/ });
+    t.is(await contract.view("token_owner", { token_id: tokenId }), bob.accountId);
+});
@@ -0,0 +1,373 @@
+import { createHash } from "crypto";
+import { NEAR, NearAccount } from "near-workspaces";
+import { balanceOf, call, callRaw, createSeries, eventsOf, mint, secondsFromNow, STORAGE_DEPOSIT, test, useSandbox, waitUntil } from "./utils";
+
+useSandbox();
+
+//key of the first entry in the values vector of seriesById (the prefix plus the little endian u32 index)
+const FIRST_SERIES_KEY = "seriesByIdv\u0000\u0000\u0000\u0000";
+
+//point the first series at a fresh, empty token set so its membership no longer matches tokensById
+async function desyncFirstSeries(contract: NearAccount) {
+    const state = await contract.viewState();
+    const series = state.get(FIRST_SERIES_KEY);
+    series.tokens = { length: 0, prefix: "desynced", elementIndexPrefix: "desyncedi", elements: { length: 0, prefix: "desyncede" } };
+    await contract.patchState(FIRST_SERIES_KEY, JSON.stringify(series));
+}
+
+test("repair_series_membership reconciles a desynced token set a page at a time", async (t) => {
+    const { contract, owner, creator, alice } = t.context.accounts;
+    await createSeries(creator, contract, 0);
+    for (let i = 0; i < 3; i++) {
+        await mint(creator, contract, 0, alice);
+    }
+
+    await desyncFirstSeries(contract);
+    t.is(await contract.view("nft_supply_for_series", { id: 0 }), 0);
+
+    //only the owner can repair, and a page that doesn't reach the tokens changes nothing
+    await t.throwsAsync(call(alice, contract, "repair_series_membership", { id: 0 }), { message: /Only the contract owner can call this method/ });
+    await call(owner, contract, "repair_series_membership", { id: 0, from_index: "3", limit: 10 });
+    t.is(await contract.view("nft_supply_for_series", { id: 0 }), 0);
+
+    await call(owner, contract, "repair_series_membership", { id: 0, from_index: "0", limit: 10 });
+    t.is(await contract.view("nft_supply_for_series", { id: 0 }), 3);
+    const tokens: any[] = await contract.view("nft_tokens_for_series", { id: 0 });
+    t.deepEqual(tokens.map((token) => token.token_id).sort(), ["0:1", "0:2", "0:3"]);
+});
+
+test("merge_series moves every token into the target series", async (t) => {
+    const { contract, owner, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    await createSeries(creator, contract, 2);
+    await mint(creator, contract, 1, alice);
+    await mint(creator, contract, 2, alice);
+    await mint(creator, contract, 2, bob);
+
+    //merging is left to the contract owner, even the owner of both series can't
+    await t.throwsAsync(call(creator, contract, "merge_series", { from_series_id: 2, into_series_id: 1 }), { message: /Only the owner can merge series/ });
+    await call(owner, contract, "merge_series", { from_series_id: 2, into_series_id: 1 });
+
+    t.is(await contract.view("nft_supply_for_series", { id: 1 }), 3);
+    t.is(await contract.view("get_series_info", { id: 2 }), null);
+    const tokens: any[] = await contract.view("nft_tokens_for_series", { id: 1 });
+    t.deepEqual(tokens.map((token) => token.token_id).sort(), ["1:1", "2:1", "2:2"]);
+    t.true(tokens.every((token) => token.series_id == 1));
+
+    //the moved tokens keep their owners
+    const moved: any = await contract.view("nft_token", { token_id: "2:2" });
+    t.is(moved.owner_id, bob.accountId);
+});
+
+test("media policy only lets allowed gateways through", async (t) => {
+    const { contract, owner, creator } = t.context.accounts;
+    await call(owner, contract, "add_allowed_media_prefix", { prefix: "ipfs://" });
+    await call(owner, contract, "set_media_policy", { enabled: true, allow_data_uris: false });
+
+    await createSeries(creator, contract, 1);
+    t.not(await contract.view("get_series_info", { id: 1 }), null);
+
+    const disallowed = createSeries(creator, contract, 2, { metadata: { title: "Series 2", media: "https://example.com/2.png" } });
+    await t.throwsAsync(disallowed, { message: /does not use an allowed storage gateway/ });
+    const dataUri = createSeries(creator, contract, 3, { metadata: { title: "Series 3", media: "data:image/png;base64,AAAA" } });
+    await t.throwsAsync(dataUri, { message: /Data URIs are not allowed/ });
+    t.is(await contract.view("get_series_info", { id: 2 }), null);
+});
+
+test("mint_precheck reports the first blocking reason", async (t) => {
+    const { worker } = t.context;
+    const { contract, owner, creator, alice } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    await createSeries(creator, contract, 2, { metadata: { title: "Series 2", copies: 1 } });
+    await createSeries(creator, contract, 3, { mint_start: await secondsFromNow(worker, 3600) });
+    await createSeries(creator, contract, 4, { price: "1000000000000000000000000" });
+    await mint(creator, contract, 2, alice);
+
+    const precheck = (id: number, account: string): Promise<any> => contract.view("mint_precheck", { id, account_id: account });
+    //the storage of one token is estimated at 1000 bytes
+    const storage = BigInt("10000000000000000000000");
+
+    t.deepEqual(await precheck(1, creator.accountId), { can_mint: true, reason: null, required_deposit: storage.toString() });
+    t.is((await precheck(42, creator.accountId)).reason, "no series");
+    t.is((await precheck(1, alice.accountId)).reason, "Not approved minter");
+    t.is((await precheck(2, creator.accountId)).reason, "Series sold out");
+    t.is((await precheck(3, creator.accountId)).reason, "Minting not started");
+
+    //priced series are open to anyone and quote the price on top of the storage
+    const priced = await precheck(4, alice.accountId);
+    t.true(priced.can_mint);
+    t.is(priced.required_deposit, (BigInt("1000000000000000000000000") + storage).toString());
+
+    await call(owner, contract, "set_paused", { paused: true });
+    t.deepEqual(await precheck(1, creator.accountId), { can_mint: false, reason: "Contract is paused", required_deposit: storage.toString() });
+});
+
+test("merged tokens get fresh editions in the target series", async (t) => {
+    const { contract, owner, creator, alice } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+    await createSeries(creator, contract, 2);
+    for (let i = 0; i < 2; i++) {
+        await mint(creator, contract, 1, alice);
+        await mint(creator, contract, 2, alice);
+    }
+
+    //both series have editions 1 and 2 before the merge
+    await call(owner, contract, "merge_series", { from_series_id: 2, into_series_id: 1 });
+
+    const tokens: any[] = await contract.view("nft_tokens_for_series", { id: 1 });
+    const titles = tokens.map((token) => token.metadata.title).sort();
+    t.deepEqual(titles, ["Series 1 - 1", "Series 1 - 2", "Series 1 - 3", "Series 1 - 4"]);
+
+    //the next mint continues after the merged editions
+    const next = await mint(creator, contract, 1, alice);
+    t.is((await contract.view("nft_token", { token_id: next }) as any).metadata.title, "Series 1 - 5");
+});
+
+test("rewrite_media_prefix rewrites the prefix once across pages", async (t) => {
+    const { contract, creator, alice } = t.context.accounts;
+    const metadata = {
+        title: "Series 1",
+        description: "Mirrored from ipfs://series-1/",
+        media: "ipfs://series-1/media.png",
+        reference: "ipfs://series-1/reference.json",
+    };
+    await createSeries(creator, contract, 1, { metadata });
+    for (let i = 0; i < 3; i++) {
+        await mint(creator, contract, 1, alice);
+    }
+    await call(creator, contract, "set_token_metadata", { token_id: "1:2", metadata: { media: "ipfs://series-1/2.png" } }, STORAGE_DEPOSIT);
+
+    //the new prefix extends the old one, so rewriting the series on every page would stack it up
+    const args = { id: 1, old_prefix: "ipfs://series-1/", new_prefix: "ipfs://series-1/v2/" };
+    await t.throwsAsync(call(alice, contract, "rewrite_media_prefix", args));
+    await call(creator, contract, "rewrite_media_prefix", { ...args, from_index: "0", limit: 2 });
+    await call(creator, contract, "rewrite_media_prefix", { ...args, from_index: "2", limit: 2 });
+
+    const series: any = await contract.view("get_series_info", { id: 1 });
+    t.is(series.metadata.media, "ipfs://series-1/v2/media.png");
+    t.is(series.metadata.reference, "ipfs://series-1/v2/reference.json");
+    t.is(series.metadata.description, metadata.description);
+
+    const overridden: any = await contract.view("nft_token", { token_id: "1:2" });
+    t.is(overridden.metadata.media, "ipfs://series-1/v2/2.png");
+    const plain: any = await contract.view("nft_token", { token_id: "1:3" });
+    t.is(plain.metadata.media, "ipfs://series-1/v2/media.png");
+});
+
+test("a manager with only the mint permission can mint but not edit the royalty", async (t) => {
+    const { contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+
+    //1 is the mint permission bit
+    await t.throwsAsync(call(alice, contract, "set_series_manager", { id: 1, account_id: alice.accountId, perms: 1 }));
+    await call(creator, contract, "set_series_manager", { id: 1, account_id: alice.accountId, perms: 1 });
+
+    const tokenId = await mint(alice, contract, 1, bob);
+    t.is(await contract.view("token_owner", { token_id: tokenId }), bob.accountId);
+    await t.throwsAsync(call(alice, contract, "update_series_royalty", { id: 1, royalty: { [alice.accountId]: 1000 } }));
+    t.deepEqual((await contract.view("get_series_info", { id: 1 }) as any).royalty, {});
+});
+
+test("set_soulbound toggles only while the series is empty", async (t) => {
+    const { contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1);
+
+    await call(creator, contract, "set_soulbound", { id: 1, soulbound: true });
+    await call(creator, contract, "set_soulbound", { id: 1, soulbound: false });
+    await call(creator, contract, "set_soulbound", { id: 1, soulbound: true });
+
+    const tokenId = await mint(creator, contract, 1, alice);
+    await t.throwsAsync(call(alice, contract, "nft_transfer", { receiver_id: bob.accountId, token_id: tokenId }, "1"), { message: /Token is soulbound/ });
+    await t.throwsAsync(call(creator, contract, "set_soulbound", { id: 1, soulbound: false }), { message: /Cannot change soulbound once tokens have been minted/ });
+});
+
+test("metadata pool entries are handed out once and the series sells out with the pool", async (t) => {
+    const { contract, creator, alice } = t.context.accounts;
+    const pool = [1, 2, 3].map((i) => ({ title: `Item ${i}`, media: `ipfs://series-1/${i}.png` }));
+    await createSeries(creator, contract, 1, { metadata_pool: pool });
+
+    const media: string[] = [];
+    for (let i = 0; i < pool.length; i++) {
+        const tokenId = await mint(creator, contract, 1, alice);
+        media.push((await contract.view("nft_token", { token_id: tokenId }) as any).metadata.media);
+    }
+    t.deepEqual(media.sort(), pool.map((entry) => entry.media));
+
+    await t.throwsAsync(mint(creator, contract, 1, alice), { message: /Series sold out/ });
+});
+
+test("soulbound series tokens can't be transferred or approved but can be burned", async (t) => {
+    const { contract, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1, { transferable: false });
+    await createSeries(creator, contract, 2);
+    t.false(await contract.view("series_is_transferable", { id: 1 }));
+    t.true(await contract.view("series_is_transferable", { id: 2 }));
+
+    const tokenId = await mint(creator, contract, 1, alice);
+    const soulbound = { message: /Token is soulbound/ };
+    await t.throwsAsync(call(alice, contract, "nft_transfer", { receiver_id: bob.accountId, token_id: tokenId }, "1"), soulbound);
+    await t.throwsAsync(call(alice, contract, "nft_transfer_call", { receiver_id: bob.accountId, token_id: tokenId, msg: "" }, "1"), soulbound);
+    await t.throwsAsync(call(alice, contract, "nft_approve", { token_id: tokenId, account_id: bob.accountId }, STORAGE_DEPOSIT), soulbound);
+
+    //holders can still opt out by burning
+    await call(alice, contract, "nft_burn", { token_id: tokenId }, "1");
+    t.is(await contract.view("token_owner", { token_id: tokenId }), null);
+});
+
+//sha256 of the passed in bytes, the hash nft_mint_with_proof builds the merkle tree with
+function sha256(data: Buffer): Buffer {
+    return createHash("sha256").update(data).digest();
+}
+
+//hash two sibling nodes together, smaller one first, the way verifyMerkleProof does
+function hashPair(a: Buffer, b: Buffer): Buffer {
+    return Buffer.compare(a, b) < 0 ? sha256(Buffer.concat([a, b])) : sha256(Buffer.concat([b, a]));
+}
+
+test("nft_mint_with_proof only mints for accounts in the merkle tree", async (t) => {
+    const { contract, creator, alice, bob } = t.context.accounts;
+    const price = NEAR.parse("0.1 N").toString();
+    const deposit = (BigInt(price) + BigInt(STORAGE_DEPOSIT)).toString();
+    await createSeries(creator, contract, 1, { price, mint_limit_per_account: 1 });
+
+    //a two leaf tree of alice and bob, where each leaf is the sha256 of the account ID bytes
+    const [aliceLeaf, bobLeaf] = [alice, bob].map((account) => sha256(Buffer.from(account.accountId)));
+    await call(creator, contract, "set_series_merkle_root", { id: 1, root: hashPair(aliceLeaf, bobLeaf).toString("hex") });
+
+    //without a proof the allowlist blocks the public mint
+    await t.throwsAsync(mint(alice, contract, 1, alice, deposit), { message: /Not on allowlist/ });
+
+    const withProof = (account: NearAccount, proof: Buffer[]) =>
+        call(account, contract, "nft_mint_with_proof", { id: 1, receiver_id: account.accountId, proof: proof.map((node) => node.toString("hex")) }, deposit);
+    await withProof(alice, [bobLeaf]);
+    await withProof(bob, [aliceLeaf]);
+    t.is(await contract.view("nft_supply_for_series", { id: 1 }), 2);
+
+    //creator isn't a leaf, and a proven account is still held to the per-account limit
+    await t.throwsAsync(withProof(creator, [aliceLeaf]), { message: /Not on allowlist/ });
+    await t.throwsAsync(withProof(alice, [bobLeaf]), { message: /Per-account mint limit reached/ });
+});
+
+test("discount codes take their percentage off the price until their uses run out", async (t) => {
+    const { contract, creator, alice, bob } = t.context.accounts;
+    const price = NEAR.parse("1 N").toString();
+    const deposit = (BigInt(price) + BigInt(STORAGE_DEPOSIT)).toString();
+    await createSeries(creator, contract, 1, { price });
+
+    //codes are set up by their hash, the plaintext is only passed in when minting
+    const codeHash = sha256(Buffer.from("EARLY")).toString("hex");
+    await t.throwsAsync(call(alice, contract, "set_discount_code", { id: 1, code_hash: codeHash, percent_off: 25, max_uses: 1 }), { message: /Not owner/ });
+    await call(creator, contract, "set_discount_code", { id: 1, code_hash: codeHash, percent_off: 25, max_uses: 1 });
+
+    const withCode = (account: NearAccount, code: string) =>
+        call(account, contract, "nft_mint", { id: 1, receiver_id: account.accountId, code }, deposit);
+
+    //the series owner is paid the discounted price
+    const before = await balanceOf(creator);
+    await withCode(alice, "EARLY");
+    t.is((await balanceOf(creator)) - before, BigInt(NEAR.parse("0.75 N").toString()));
+
+    //the only use is gone, and unknown codes are rejected outright
+    await t.throwsAsync(withCode(bob, "EARLY"), { message: /Discount code exhausted/ });
+    await t.throwsAsync(withCode(bob, "LATE"), { message: /Invalid discount code/ });
+    t.is(await contract.view("nft_supply_for_series", { id: 1 }), 1);
+});
+
+test("effective_mint_price itemizes the price, discount, platform fee and storage", async (t) => {
+    const { contract, owner, creator, alice, bob } = t.context.accounts;
+    await createSeries(creator, contract, 1, { price: NEAR.parse("1 N").toString() });
+    await call(owner, contract, "set_platform_fee", { treasury_id: bob.accountId, platform_fee_bps: 500 });
+    await call(creator, contract, "set_discount_code", { id: 1, code_hash: sha256(Buffer.from("EARLY")).toString("hex"), percent_off: 20, max_uses: 5 });
+
+    //the storage of one token is estimated at 1000 bytes
+    const storage = BigInt("10000000000000000000000");
+    const quote: any = await contract.view("effective_mint_price", { id: 1, account_id: alice.accountId, code: "EARLY" });
+    t.deepEqual(quote, {
+        base_price: NEAR.parse("1 N").toString(),
+        discount: NEAR.parse("0.2 N").toString(),
+        //the fee is taken out of the discounted price, it doesn't add to the deposit
+        platform_fee: NEAR.parse("0.04 N").toString(),
+        storage_cost: storage.toString(),
+        required_deposit: (BigInt(NEAR.parse("0.8 N").toString()) + storage).toString(),
+    });
+    await t.throwsAsync(contract.view("effective_mint_price", { id: 1, account_id: alice.accountId, code: "LATE" }), { message: /Invalid discount code/ });
+
+    //the quoted deposit is enough for the mint
+    await call(alice, contract, "nft_mint", { id: 1, receiver_id: alice.accountId, code: "EARLY" }, quote.required_deposit);
+    t.is(await contract.view("nft_supply_for_series", { id: 1 }), 1);
+});
+
+test("refundable mints can be returned for the price minus the fee until the window passes", async (t) => {
+    const { worker } = t.context;
+    const { contract, owner, creator, alice, bob } = t.context.accounts;
+    const price = NEAR.parse("1 N").toString();
+    const deposit = (BigInt(price) + BigInt(STORAGE_DEPOSIT)).toString();
+    await createSeries(creator, contract, 1, { price, refundable: true });
+    await call(owner, contract, "set_refund_window", { window_ns: "10000000000" });
+
+    //inside the window the token is burned and the price comes back minus the 2.5% fee
+    const refunded = await mint(alice, contract, 1, alice, deposit);
+    const result = await callRaw(alice, contract, "refund_mint", { token_id: refunded }, "1");
+    t.is(eventsOf(result.logs, "mint_refund")[0].data[0].refund_amount, NEAR.parse("0.975 N").toString());
+    t.is(await contract.view("token_owner", { token_id: refunded }), null);
+
+    //past the window the mint is final and its held price can be released to the series owner
+    const kept = await mint(alice, contract, 1, alice, deposit);
+    await t.throwsAsync(call(bob, contract, "release_mint_proceeds", { token_id: kept }), { message: /Mint can still be refunded/ });
+    await waitUntil(worker, await secondsFromNow(worker, 10));
+    await t.throwsAsync(call(alice, contract, "refund_mint", { token_id: kept }, "1"), { message: /Refund window has passed/ });
+
+    const before = await balanceOf(creator);
+    await call(bob, contract, "release_mint_proceeds", { token_id: kept });
+    t.is((await balanceOf(creator)) - before, BigInt(price));
+    t.is(await contract.view("token_owner", { token_id: kept }), alice.accountId);
+});
+
+test("the first mint after the window ends emits series_closed once instead of minting", async (t) => {
+    const { worker } = t.context;
+    const { contract, creator, alice } = t.context.accounts;
+    const mintEnd = await secondsFromNow(worker, 10);
+    await createSeries(creator, contract, 1, { mint_end: mintEnd });
+    await mint(creator, contract, 1, alice);
+    await waitUntil(worker, mintEnd);
+
+    //the mint goes through without panicking so the close is recorded, but nothing is minted
+    const result = await callRaw(creator, contract, "nft_mint", { id: 1, receiver_id: alice.accountId }, STORAGE_DEPOSIT);
+    t.false(result.failed);
+    const [closed] = eventsOf(result.logs, "series_closed");
+    t.is(closed.data[0].series_id, 1);
+    t.is(closed.data[0].mint_end, mintEnd);
+    t.is(eventsOf(result.logs, "nft_mint").length, 0);
+    t.is(await contract.view("nft_supply_for_series", { id: 1 }), 1);
+
+    //from then on mints are rejected without another close event
+    const again = await callRaw(creator, contract, "nft_mint", { id: 1, receiver_id: alice.accountId }, STORAGE_DEPOSIT);
+    t.true(again.failed);
+    t.regex(JSON.stringify(again.receiptFailureMessages), /Minting ended/);
+    t.is(eventsOf(again.logs, "series_closed").length, 0);
+});
+
+test("per-account mint counts and the allowlist follow a renamed series and don't carry over to a new one", async (t) => {
+    const { contract, creator, alice } = t.context.accounts;
+    await createSeries(creator, contract, 1, { mint_limit_per_account: 1 });
+    await call(creator, contract, "add_to_series_allowlist", { id: 1, account_ids: [alice.accountId] });
+    await mint(creator, contract, 1, alice);
+
+    //the renamed series keeps its entries
+    await call(creator, contract, "update_series_id", { current_id: 1, new_id: 2 });
+    t.is(await contract.view("remaining_mints_for_account", { id: 2, account_id: creator.accountId }), 0);
+    t.true(await contract.view("is_on_series_allowlist", { id: 2, account_id: alice.accountId }));
+    await t.throwsAsync(mint(creator, contract, 2, alice), { message: /Per-account mint limit reached/ });
+
+    //a new series under the old ID starts from a clean slate
+    await createSeries(creator, contract, 1, { mint_limit_per_account: 1 });
+    t.is(await contract.view("remaining_mints_for_account", { id: 1, account_id: creator.accountId }), 1);
+    t.false(await contract.view("is_on_series_allowlist", { id: 1, account_id: alice.accountId }));
+
+    //and so does one created after a series was deleted
+    await createSeries(creator, contract, 3);
+    await call(creator, contract, "add_to_series_allowlist", { id: 3, account_ids: [alice.accountId] });
+    await call(creator, contract, "delete_series", { id: 3 });
+    await createSeries(creator, contract, 3);
+    t.false(await contract.view("is_on_series_allowlist", { id: 3, account_id: alice.accountId }));
+});
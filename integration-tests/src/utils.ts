@@ -0,0 +1,114 @@
+import { NEAR, NearAccount, Worker } from "near-workspaces";
+import anyTest, { TestFn } from "ava";
+
+//JS contracts need a lot more gas than the 30 TGas near-workspaces attaches by default
+export const GAS = "300000000000000";
+
+//enough to cover the storage of a series or a mint, whatever isn't used is refunded
+export const STORAGE_DEPOSIT = NEAR.parse("0.5 N").toString();
+
+export type Context = {
+    worker: Worker;
+    accounts: Record<string, NearAccount>;
+};
+
+export const test = anyTest as TestFn<Context>;
+
+/*
+    spin up a sandbox for every test with the contract deployed and initialized. The owner account owns the
+    contract, creator is an approved minter that creates and mints series, alice and bob are regular users
+*/
+export function useSandbox() {
+    test.beforeEach(async (t) => {
+        const worker = await Worker.init();
+        const root = worker.rootAccount;
+
+        const contract = await root.createSubAccount("nft", { initialBalance: NEAR.parse("50 N").toString() });
+        await contract.deploy("../build/nft.wasm");
+
+        const owner = await root.createSubAccount("owner", { initialBalance: NEAR.parse("50 N").toString() });
+        const creator = await root.createSubAccount("creator", { initialBalance: NEAR.parse("50 N").toString() });
+        const alice = await root.createSubAccount("alice", { initialBalance: NEAR.parse("50 N").toString() });
+        const bob = await root.createSubAccount("bob", { initialBalance: NEAR.parse("50 N").toString() });
+
+        await contract.call(contract, "init", { owner_id: owner.accountId }, { gas: GAS });
+        await owner.call(contract, "add_approved_minters", { account_id: creator.accountId }, { gas: GAS });
+
+        t.context.worker = worker;
+        t.context.accounts = { root, contract, owner, creator, alice, bob };
+    });
+
+    test.afterEach.always(async (t) => {
+        await t.context.worker.tearDown().catch((error) => {
+            console.log("Failed to stop the Sandbox:", error);
+        });
+    });
+}
+
+//call a method on the contract with enough gas and an optional deposit (in yoctoNEAR)
+export async function call(account: NearAccount, contract: NearAccount, method: string, args: object, deposit: string = "0") {
+    return account.call(contract, method, args, { gas: GAS, attachedDeposit: deposit });
+}
+
+//same as call, but also returns the logs of the transaction
+export async function callRaw(account: NearAccount, contract: NearAccount, method: string, args: object, deposit: string = "0") {
+    return account.callRaw(contract, method, args, { gas: GAS, attachedDeposit: deposit });
+}
+
+//get the parsed NEP-297 events out of a transaction's logs. Transfer events are logged as bare JSON without the
+//EVENT_JSON: prefix, so those are picked up as well
+export function eventsOf(logs: string[], event?: string): any[] {
+    return logs
+        .map((log) => (log.startsWith("EVENT_JSON:") ? log.substring("EVENT_JSON:".length) : log))
+        .filter((log) => log.startsWith("{"))
+        .map((log) => JSON.parse(log))
+        .filter((parsed) => event == null || parsed.event == event);
+}
+
+//create a series owned by the passed in account (an approved minter). Extra create_series arguments can be passed in
+export async function createSeries(account: NearAccount, contract: NearAccount, id: number, args: object = {}) {
+    const metadata = { title: `Series ${id}`, media: `ipfs://series-${id}/media.png` };
+    return call(account, contract, "create_series", { id, metadata, royalty: null, ...args }, STORAGE_DEPOSIT);
+}
+
+//mint the next token of a series to the receiver and return its token ID (taken from the nft_mint event)
+export async function mint(account: NearAccount, contract: NearAccount, id: number, receiver: NearAccount, deposit: string = STORAGE_DEPOSIT): Promise<string> {
+    const result = await callRaw(account, contract, "nft_mint", { id, receiver_id: receiver.accountId }, deposit);
+    if (result.failed) {
+        throw new Error(JSON.stringify(result.receiptFailureMessages));
+    }
+    return eventsOf(result.logs, "nft_mint")[0].data[0].token_ids[0];
+}
+
+//the storage a buyer's first offer on a token pays for on top of the offer, see offerStorageCost in market.ts
+export function offerStorage(buyer: NearAccount): bigint {
+    return BigInt(buyer.accountId.length + 46) * BigInt("10000000000000000000");
+}
+
+//make an offer of the passed in amount (in yoctoNEAR) on a token, attaching the storage of the offer as well
+export async function makeOffer(buyer: NearAccount, contract: NearAccount, tokenId: string, amount: string) {
+    return call(buyer, contract, "make_offer", { token_id: tokenId }, (BigInt(amount) + offerStorage(buyer)).toString());
+}
+
+//get the total balance of an account in yoctoNEAR
+export async function balanceOf(account: NearAccount): Promise<bigint> {
+    return BigInt((await account.balance()).total.toString());
+}
+
+//get the current block timestamp (in nanoseconds) of the sandbox
+export async function now(worker: Worker): Promise<bigint> {
+    const block = await worker.provider.block({ finality: "final" });
+    return BigInt(block.header.timestamp_nanosec);
+}
+
+//a block timestamp (in nanoseconds) the passed in number of seconds from now
+export async function secondsFromNow(worker: Worker, seconds: number): Promise<string> {
+    return ((await now(worker)) + BigInt(seconds) * BigInt(1000000000)).toString();
+}
+
+//wait for the sandbox clock to move past a block timestamp (in nanoseconds)
+export async function waitUntil(worker: Worker, timestamp: string) {
+    while ((await now(worker)) <= BigInt(timestamp)) {
+        await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+}
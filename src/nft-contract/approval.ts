@@ -1,22 +1,48 @@
 import { assert, bytes, near } from "near-sdk-js";
 import { Contract, NFT_METADATA_SPEC, NFT_STANDARD_NAME } from ".";
-import { assertAtLeastOneYocto, assertOneYocto, bytesForApprovedAccountId, internalAddTokenToOwner, refundDeposit, refundApprovedAccountIds, refundApprovedAccountIdsIter } from "./internal";
-import { Token } from "./metadata";
+import { activeApprovalId, assertAtLeastOneYocto, assertOneYocto, bytesForApprovedAccountId, internalAddTokenToOwner, internalRecordApprovalEvent, refundDeposit, refundApprovedAccountIds, refundApprovedAccountIdsIter, assertNotPaused, isSeriesTransferable } from "./internal";
+import { ApprovalEvent, Series, Token } from "./metadata";
 
 const GAS_FOR_NFT_ON_APPROVE = 35_000_000_000_000;
 
-//approve an account ID to transfer a token on your behalf
+//maximum number of accounts that can be approved in a single nft_approve_batch call (each may get an nft_on_approve call)
+const MAX_APPROVE_BATCH = 6;
+
+//soulbound tokens can't be transferred, so there's nothing to approve anyone for
+function assertTokenTransferable(contract: Contract, token: Token) {
+    // @ts-ignore
+    let series = contract.seriesById.get(token.series_id) as Series;
+    assert(series == null || isSeriesTransferable(series), "Token is soulbound");
+}
+
+//set (or clear, if no expiry is passed in) when an account's approval on a token expires
+function setApprovalExpiry(token: Token, accountId: string, expiresAt?: string) {
+    if (expiresAt != null) {
+        assert(BigInt(expiresAt) > near.blockTimestamp().valueOf(), "Approval expiry must be in the future");
+        if (token.approval_expires_at == null) {
+            token.approval_expires_at = {};
+        }
+        token.approval_expires_at[accountId] = expiresAt;
+    } else if (token.approval_expires_at != null) {
+        delete token.approval_expires_at[accountId];
+    }
+}
+
+//approve an account ID to transfer a token on your behalf. The approval optionally expires at the passed in block timestamp
 export function internalNftApprove({
     contract,
     tokenId,
     accountId,
-    msg
+    msg,
+    expiresAt
 }:{ 
     contract: Contract, 
     tokenId: string, 
     accountId: string, 
-    msg: string 
+    msg: string,
+    expiresAt?: string
 }) {
+    assertNotPaused(contract);
     /*
         assert at least one yocto for security reasons - this will cause a redirect to the NEAR wallet.
         The user needs to attach enough to pay for storage on the contract
@@ -30,6 +56,7 @@ export function internalNftApprove({
     }
     //make sure that the person calling the function is the owner of the token
     assert(near.predecessorAccountId() === token.owner_id, "Predecessor must be the token owner");
+    assertTokenTransferable(contract, token);
 
     //get the next approval ID if we need a new approval
     let approvalId = token.next_approval_id;
@@ -37,6 +64,8 @@ export function internalNftApprove({
     //check if the account has been approved already for this token
     let isNewApproval = token.approved_account_ids.hasOwnProperty(accountId);
     token.approved_account_ids[accountId] = approvalId;
+    //re-approving replaces any previous expiry
+    setApprovalExpiry(token, accountId, expiresAt);
 
     //if it was a new approval, we need to calculate how much storage is being used to add the account.
     let storageUsed = isNewApproval ? bytesForApprovedAccountId(accountId) : 0;
@@ -45,6 +74,7 @@ export function internalNftApprove({
     token.next_approval_id += 1;
     //insert the token back into the tokens_by_id collection
     contract.tokensById.set(tokenId, token);
+    internalRecordApprovalEvent(contract, tokenId, "approve", accountId, approvalId);
 
     //refund any excess storage attached by the user. If the user didn't attach enough, panic. 
     refundDeposit(BigInt(storageUsed));
@@ -71,6 +101,78 @@ export function internalNftApprove({
     }
 }
 
+//approve several accounts (e.g. marketplaces) on a token at once and return the assigned approval IDs in order.
+//If a msg is passed in, every account is notified through nft_on_approve
+export function internalNftApproveBatch({
+    contract,
+    tokenId,
+    accountIds,
+    msg
+}:{ 
+    contract: Contract, 
+    tokenId: string, 
+    accountIds: string[], 
+    msg?: string 
+}): number[] {
+    assertNotPaused(contract);
+    //the user needs to attach enough to pay for the storage of the new approvals
+    assertAtLeastOneYocto();
+    assert(accountIds.length > 0, "No accounts to approve");
+    assert(accountIds.length <= MAX_APPROVE_BATCH, `Cannot approve more than ${MAX_APPROVE_BATCH} accounts at once`);
+    assert(new Set(accountIds).size == accountIds.length, "Duplicate account ID in batch");
+
+    //get the token object from the token ID
+    let token = contract.tokensById.get(tokenId) as Token;
+    if (token == null) {
+        near.panic("no token");
+    }
+    //make sure that the person calling the function is the owner of the token
+    assert(near.predecessorAccountId() === token.owner_id, "Predecessor must be the token owner");
+    assertTokenTransferable(contract, token);
+
+    let approvalIds: number[] = [];
+    let storageUsed = 0;
+    accountIds.forEach(accountId => {
+        let approvalId = token.next_approval_id;
+        //only accounts that weren't approved yet take up new storage
+        if (!token.approved_account_ids.hasOwnProperty(accountId)) {
+            storageUsed += bytesForApprovedAccountId(accountId);
+        }
+        token.approved_account_ids[accountId] = approvalId;
+        setApprovalExpiry(token, accountId, null);
+        token.next_approval_id += 1;
+        approvalIds.push(approvalId);
+    });
+
+    //insert the token back into the tokens_by_id collection
+    contract.tokensById.set(tokenId, token);
+    accountIds.forEach((accountId, i) => internalRecordApprovalEvent(contract, tokenId, "approve", accountId, approvalIds[i]));
+
+    //refund any excess storage attached by the user. If the user didn't attach enough, panic. 
+    refundDeposit(BigInt(storageUsed));
+
+    //notify each approved account with its own approval ID
+    if (msg != null) {
+        accountIds.forEach((accountId, i) => {
+            const promise = near.promiseBatchCreate(accountId);
+            near.promiseBatchActionFunctionCall(
+                promise, 
+                "nft_on_approve", 
+                bytes(JSON.stringify({ 
+                    token_id: tokenId,
+                    owner_id: token.owner_id,
+                    approval_id: approvalIds[i],
+                    msg
+                })), 
+                0, // no deposit 
+                GAS_FOR_NFT_ON_APPROVE
+            );
+        });
+    }
+
+    return approvalIds;
+}
+
 //check if the passed in account has access to approve the token ID
 export function internalNftIsApproved({
     contract,
@@ -89,8 +191,8 @@ export function internalNftIsApproved({
         near.panic("no token");
     }
 
-    //get the approval number for the passed in account ID
-    let approval = token.approved_account_ids[approvedAccountId];
+    //get the approval number for the passed in account ID, expired approvals count as missing
+    let approval = activeApprovalId(token, approvedAccountId);
 
     //if there was no approval ID found for the account ID, we simply return false
     if (approval == null) {
@@ -118,6 +220,7 @@ export function internalNftRevoke({
     tokenId: string, 
     accountId: string 
 }) {
+    assertNotPaused(contract);
     //assert that the user attached exactly 1 yoctoNEAR for security reasons
     assertOneYocto();
 
@@ -134,12 +237,14 @@ export function internalNftRevoke({
     //if the account ID was in the token's approval, we remove it
     if (token.approved_account_ids.hasOwnProperty(accountId)) {
         delete token.approved_account_ids[accountId];
+        setApprovalExpiry(token, accountId, null);
         
         //refund the funds released by removing the approved_account_id to the caller of the function
         refundApprovedAccountIdsIter(predecessorAccountId, [accountId]);
         
         //insert the token back into the tokens_by_id collection with the account_id removed from the approval list
         contract.tokensById.set(tokenId, token);
+        internalRecordApprovalEvent(contract, tokenId, "revoke", accountId);
     }
 }
 
@@ -151,6 +256,7 @@ export function internalNftRevokeAll({
     contract: Contract, 
     tokenId: string 
 }) {
+    assertNotPaused(contract);
     //assert that the caller attached exactly 1 yoctoNEAR for security
     assertOneYocto();
 
@@ -165,12 +271,65 @@ export function internalNftRevokeAll({
     assert(predecessorAccountId == token.owner_id, "only token owner can revoke");
 
     //only revoke if the approved account IDs for the token is not empty
-    if (token.approved_account_ids && Object.keys(token.approved_account_ids).length === 0 && Object.getPrototypeOf(token.approved_account_ids) === Object.prototype) {
+    if (token.approved_account_ids && Object.keys(token.approved_account_ids).length !== 0) {
         //refund the approved account IDs to the caller of the function
         refundApprovedAccountIds(predecessorAccountId, token.approved_account_ids);
+        Object.keys(token.approved_account_ids).forEach(accountId => internalRecordApprovalEvent(contract, tokenId, "revoke", accountId));
         //clear the approved account IDs
         token.approved_account_ids = {};
+        token.approval_expires_at = {};
         //insert the token back into the tokens_by_id collection with the approved account IDs cleared
         contract.tokensById.set(tokenId, token);
     }
+}
+
+//get every approved account on a token and its approval ID
+export function internalNftApprovals({
+    contract,
+    tokenId
+}:{ 
+    contract: Contract, 
+    tokenId: string
+}): { [accountId: string]: number } {
+    let token = contract.tokensById.get(tokenId) as Token;
+    if (token == null) {
+        near.panic("no token");
+    }
+    return token.approved_account_ids;
+}
+
+//get when an account's approval on a token expires. Null if the approval never expires or doesn't exist
+export function internalNftApprovalExpiresAt({
+    contract,
+    tokenId,
+    accountId
+}:{ 
+    contract: Contract, 
+    tokenId: string,
+    accountId: string
+}): string {
+    let token = contract.tokensById.get(tokenId) as Token;
+    if (token == null) {
+        near.panic("no token");
+    }
+    if (!token.approved_account_ids.hasOwnProperty(accountId) || token.approval_expires_at == null) {
+        return null;
+    }
+    let expiresAt = token.approval_expires_at[accountId];
+    return expiresAt != null ? expiresAt : null;
+}
+
+//get the rolling history of approvals and revocations for a token
+export function internalTokenApprovalHistory({
+    contract,
+    tokenId
+}:{ 
+    contract: Contract, 
+    tokenId: string 
+}): ApprovalEvent[] {
+    let history = contract.approvalHistory.get(tokenId) as ApprovalEvent[];
+    if (history == null) {
+        return [];
+    }
+    return history;
 }
\ No newline at end of file
@@ -1,28 +1,46 @@
-import { near, UnorderedSet } from "near-sdk-js";
+import { assert, near, UnorderedSet } from "near-sdk-js";
 import { Contract, NFT_METADATA_SPEC, NFT_STANDARD_NAME } from ".";
-import { restoreOwners } from "./internal";
-import { JsonSeries, JsonToken, Series } from "./metadata";
+import { restoreOwners, seriesSupplyCap } from "./internal";
+import { ContractStats, JsonSeries, JsonToken, Series, Token } from "./metadata";
 import { internalNftToken } from "./nft_core";
 
+//maximum number of series that can be requested at once in series_configs
+const MAX_SERIES_CONFIGS_QUERY = 100;
+
+//maximum number of tokens that can be returned by a single page of nft_tokens_for_series
+const MAX_SERIES_TOKENS_PAGE = 100;
+
 //Query for the total supply of NFTs on the contract
 export function internalTotalSupply({
     contract
 }:{
     contract: Contract
 }): number {
-    //return the length of the tokens by ID
-    return contract.tokensById.len();
+    //return the maintained counter, deployments from before it existed fall back to the length of the tokens by ID
+    return contract.totalSupply != null ? contract.totalSupply : contract.tokensById.len();
 }
 
-//Query for nft tokens on the contract regardless of the owner using pagination
+//Query for the number of tokens that have been burned on the contract
+export function internalTotalBurned({
+    contract
+}:{
+    contract: Contract
+}): number {
+    return contract.totalBurned != null ? contract.totalBurned : 0;
+}
+
+//Query for nft tokens on the contract regardless of the owner using pagination. With reverse, the newest tokens
+//come first and fromIndex counts from the newest token
 export function internalNftTokens({
     contract,
     fromIndex,
-    limit
+    limit,
+    reverse
 }:{ 
     contract: Contract, 
     fromIndex?: string, 
-    limit?: number
+    limit?: number,
+    reverse?: boolean
 }): JsonToken[] {
     let tokens = [];
 
@@ -32,6 +50,9 @@ export function internalNftTokens({
     let max = limit ? limit : 50;
 
     let keys = contract.tokensById.toArray();
+    if (reverse == true) {
+        keys.reverse();
+    }
     // Paginate through the keys using the fromIndex and limit
     for (let i = start; i < keys.length && i < start + max; i++) {
         // get the token object from the keys
@@ -60,17 +81,22 @@ export function internalSupplyForOwner({
     return tokens.len();
 }
 
-//Query for all the tokens for an owner
+//Query for all the tokens for an owner, optionally only the ones from a single series. With reverse, the most
+//recently received tokens come first and fromIndex counts from the end
 export function internalTokensForOwner({
     contract,
     accountId,
     fromIndex,
-    limit
+    limit,
+    seriesId,
+    reverse
 }:{
     contract: Contract, 
     accountId: string, 
     fromIndex?: string, 
-    limit?: number
+    limit?: number,
+    seriesId?: number,
+    reverse?: boolean
 }): JsonToken[] {
     //get the set of tokens for the passed in owner
     let tokenSet = restoreOwners(contract.tokensPerOwner.get(accountId));
@@ -85,9 +111,16 @@ export function internalTokensForOwner({
     //take the first "limit" elements in the array. If we didn't specify a limit, use 50
     let max = limit ? limit : 50;
 
+    if (seriesId != null) {
+        return ownedTokensInSeries(contract, tokenSet, seriesId, start, max, reverse == true);
+    }
+
     let keys = tokenSet.toArray();
+    if (reverse == true) {
+        keys.reverse();
+    }
     let tokens: JsonToken[] = []
-    for(let i = start; i < max; i++) {
+    for(let i = start; i < start + max; i++) {
         if(i >= keys.length) {
             break;
         }
@@ -97,6 +130,48 @@ export function internalTokensForOwner({
     return tokens;
 }
 
+//page through the intersection of an owner's tokens and a series' tokens. Only the smaller of the two sets is
+//walked (looking each token up in the other one) and the walk stops as soon as the page is full
+function ownedTokensInSeries(contract: Contract, owned: UnorderedSet, seriesId: number, start: number, max: number, reverse: boolean): JsonToken[] {
+    // @ts-ignore
+    let series = contract.seriesById.get(seriesId) as Series;
+    if (series == null) {
+        return [];
+    }
+    let seriesTokens = UnorderedSet.deserialize(series.tokens as UnorderedSet);
+
+    let [smaller, larger] = owned.len() <= seriesTokens.len() ? [owned, seriesTokens] : [seriesTokens, owned];
+    let tokenIds = smaller.toArray();
+    if (reverse) {
+        tokenIds.reverse();
+    }
+    let tokens: JsonToken[] = [];
+    let matched = 0;
+    for (let i = 0; i < tokenIds.length && tokens.length < max; i++) {
+        if (!larger.contains(tokenIds[i])) {
+            continue;
+        }
+        if (matched >= start) {
+            tokens.push(internalNftToken({contract, tokenId: tokenIds[i]}));
+        }
+        matched += 1;
+    }
+    return tokens;
+}
+
+//get the number of series, tokens and distinct owners on the contract in one call
+export function internalNftContractStats({
+    contract
+}:{ 
+    contract: Contract
+}): ContractStats {
+    return new ContractStats({
+        seriesCount: contract.seriesById.len(),
+        tokenCount: contract.tokensById.len(),
+        ownerCount: contract.ownerCount != null ? contract.ownerCount : 0
+    });
+}
+
 // Get the total supply of series on the contract
 export function internalSupplySeries({
     contract
@@ -150,63 +225,111 @@ export function internalSeriesInfo({
         return null;
     }
 
-    //we return the JsonSeries
+    //we return the JsonSeries. Unrevealed series only show their placeholder so the real metadata stays hidden
     let jsonSeries = new JsonSeries({
         seriesId: id,
-        metadata: series.metadata,
+        metadata: series.revealed == false && series.placeholder_metadata != null ? series.placeholder_metadata : series.metadata,
         royalty: series.royalty,
-        ownerId: series.owner_id
+        ownerId: series.owner_id,
+        tags: series.tags != null ? series.tags : []
     });
     return jsonSeries;
 }
 
-//get the information for a specific token ID
+//get the information for a list of series. Unknown series IDs return null in their position
+export function internalSeriesConfigs({
+    contract,
+    ids
+}:{ 
+    contract: Contract, 
+    ids: number[] 
+}): JsonSeries[] {
+    assert(ids.length <= MAX_SERIES_CONFIGS_QUERY, `Cannot query more than ${MAX_SERIES_CONFIGS_QUERY} series at once`);
+    return ids.map(id => internalSeriesInfo({ contract, id }));
+}
+
+//get how many tokens a series has (the series analogue of nft_total_supply). Panics for unknown series
 export function internalNftSupplyForSeries({
     contract,
     id
 }:{ 
     contract: Contract, 
     id: number 
-}) {
+}): number {
+    let series = existingSeries(contract, id);
+
+    let tokens = UnorderedSet.deserialize(series.tokens as UnorderedSet);
+    return tokens.len();
+}
+
+//get a series or panic with a descriptive message if it doesn't exist
+function existingSeries(contract: Contract, id: number): Series {
     // @ts-ignore
     let series = contract.seriesById.get(id) as Series;
-    //if there wasn't a series in the seriesById collection, we return None
     if (series == null) {
-        return 0;
+        near.panic(`Series ${id} does not exist`);
     }
+    return series;
+}
 
-    let tokens = UnorderedSet.deserialize(series.tokens as UnorderedSet);
-    return tokens.len();
+//get how many more tokens a capped series can mint, or null for uncapped series
+export function internalSeriesRemainingSupply({
+    contract,
+    id
+}:{ 
+    contract: Contract, 
+    id: number 
+}): number {
+    let series = existingSeries(contract, id);
+    let cap = seriesSupplyCap(series);
+    if (cap == null) {
+        return null;
+    }
+    let minted = UnorderedSet.deserialize(series.tokens as UnorderedSet).len();
+    return Math.max(cap - minted, 0);
 }
 
-// Paginate through all the tokens for a series
+//get how many tokens a series currently has
+export function internalSeriesTotalMinted({
+    contract,
+    id
+}:{ 
+    contract: Contract, 
+    id: number 
+}): number {
+    let series = existingSeries(contract, id);
+    return UnorderedSet.deserialize(series.tokens as UnorderedSet).len();
+}
+
+// Paginate through all the tokens for a series. With reverse, the newest mints come first and fromIndex counts
+// from the newest mint
 export function internalNftTokensForSeries({
     contract,
     id,
     fromIndex,
-    limit
+    limit,
+    reverse
 }:{ 
     contract: Contract, 
     id: number, 
     fromIndex?: string, 
-    limit?: number
+    limit?: number,
+    reverse?: boolean
 }): JsonToken[] {
-    // @ts-ignore
-    let series = contract.seriesById.get(id) as Series;
-    //if there wasn't a series in the seriesById collection, we return None
-    if (series == null) {
-        return [];
-    }
+    let series = existingSeries(contract, id);
     
     //where to start pagination - if we have a fromIndex, we'll use that - otherwise start from 0 index
     let start = fromIndex ? parseInt(fromIndex) : 0;
-    //take the first "limit" elements in the array. If we didn't specify a limit, use 50
-    let max = limit ? limit : 50;
+    //take the first "limit" elements in the array. If we didn't specify a limit, use 50. Capped to keep the view within gas
+    let max = Math.min(limit ? limit : 50, MAX_SERIES_TOKENS_PAGE);
 
     let tokens = UnorderedSet.deserialize(series.tokens as UnorderedSet);
     let keys = tokens.toArray();
+    if (reverse == true) {
+        keys.reverse();
+    }
     let jsonTokens: JsonToken[] = []
-    for(let i = start; i < max; i++) {
+    for(let i = start; i < start + max; i++) {
         if(i >= keys.length) {
             break;
         }
@@ -214,4 +337,192 @@ export function internalNftTokensForSeries({
         jsonTokens.push(token);
     }
     return jsonTokens;
-}
\ No newline at end of file
+}
+
+// Paginate through the holders of a series and how many of its tokens each one owns.
+// This scans the entire series token set (O(tokens)) so it's meant for views only.
+export function internalSeriesHolders({
+    contract,
+    id,
+    fromIndex,
+    limit
+}:{ 
+    contract: Contract, 
+    id: number, 
+    fromIndex?: string, 
+    limit?: number
+}): [string, number][] {
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    //if there wasn't a series in the seriesById collection, we return an empty list
+    if (series == null) {
+        return [];
+    }
+
+    //tally how many tokens each owner holds, in the order owners are first seen
+    let tokens = UnorderedSet.deserialize(series.tokens as UnorderedSet);
+    let keys = tokens.toArray();
+    let owners: string[] = [];
+    let counts: { [accountId: string]: number } = {};
+    for (let i = 0; i < keys.length; i++) {
+        let token = contract.tokensById.get(keys[i]) as Token;
+        if (token == null) {
+            continue;
+        }
+        if (counts[token.owner_id] == null) {
+            owners.push(token.owner_id);
+            counts[token.owner_id] = 0;
+        }
+        counts[token.owner_id] += 1;
+    }
+
+    //where to start pagination - if we have a fromIndex, we'll use that - otherwise start from 0 index
+    let start = fromIndex ? parseInt(fromIndex) : 0;
+    //take the first "limit" elements in the array. If we didn't specify a limit, use 50
+    let max = limit ? limit : 50;
+
+    let holders: [string, number][] = [];
+    for (let i = start; i < owners.length && i < start + max; i++) {
+        holders.push([owners[i], counts[owners[i]]]);
+    }
+    return holders;
+}
+
+//get the tokens of an owner that have been held for longer than the passed in duration (in nanoseconds).
+//Tokens minted before hold times were tracked are treated as held since the beginning.
+export function internalTokensHeldLongerThan({
+    contract,
+    accountId,
+    duration
+}:{
+    contract: Contract, 
+    accountId: string, 
+    duration: string
+}): string[] {
+    //get the set of tokens for the passed in owner
+    let tokenSet = restoreOwners(contract.tokensPerOwner.get(accountId));
+    if (tokenSet == null) {
+        return [];
+    }
+
+    let cutoff = near.blockTimestamp().valueOf() - BigInt(duration);
+    let keys = tokenSet.toArray();
+    let heldTokens: string[] = [];
+    for (let i = 0; i < keys.length; i++) {
+        let token = contract.tokensById.get(keys[i]) as Token;
+        let heldSince = token.last_transfer_at != null ? BigInt(token.last_transfer_at) : BigInt(0);
+        if (heldSince < cutoff) {
+            heldTokens.push(keys[i]);
+        }
+    }
+    return heldTokens;
+}
+
+//get the tokens minted since the passed in mint log index along with the index to resume from.
+//Tokens that were burned since they were minted are skipped.
+export function internalTokensSince({
+    contract,
+    sinceLogIndex,
+    limit
+}:{
+    contract: Contract, 
+    sinceLogIndex?: string, 
+    limit?: number
+}): [JsonToken[], string] {
+    //where to start - if we have a sinceLogIndex, we'll use that - otherwise start from 0 index
+    let start = sinceLogIndex ? parseInt(sinceLogIndex) : 0;
+    //take the first "limit" elements in the log. If we didn't specify a limit, use 50
+    let max = limit ? limit : 50;
+
+    let tokens: JsonToken[] = [];
+    let i = start;
+    for (; i < contract.mintLog.len() && i < start + max; i++) {
+        let tokenId = contract.mintLog.get(i) as string;
+        if (contract.tokensById.get(tokenId) == null) {
+            continue;
+        }
+        tokens.push(internalNftToken({contract, tokenId}));
+    }
+    return [tokens, i.toString()];
+}
+//paginate through the series that have a tag
+export function internalSeriesByTag({
+    contract,
+    tag,
+    fromIndex,
+    limit
+}:{ 
+    contract: Contract, 
+    tag: string,
+    fromIndex?: string, 
+    limit?: number
+}): JsonSeries[] {
+    let ids = restoreOwners(contract.seriesByTag.get(tag));
+    if (ids == null) {
+        return [];
+    }
+
+    //where to start pagination - if we have a fromIndex, we'll use that - otherwise start from 0 index
+    let start = fromIndex ? parseInt(fromIndex) : 0;
+    //take the first "limit" elements in the array. If we didn't specify a limit, use 50
+    let max = limit ? limit : 50;
+
+    let series = [];
+    let keys = ids.toArray();
+    for (let i = start; i < keys.length && i < start + max; i++) {
+        series.push(internalSeriesInfo({ contract, id: keys[i] }));
+    }
+    return series;
+}
+
+//count how many of an account's tokens are in the given series by intersecting the owner's set with the series' set
+function ownedInSeries(contract: Contract, accountId: string, id: number, stopAtFirst: boolean): number {
+    let owned = restoreOwners(contract.tokensPerOwner.get(accountId));
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (owned == null || series == null) {
+        return 0;
+    }
+    let seriesTokens = UnorderedSet.deserialize(series.tokens as UnorderedSet);
+
+    //walk whichever set is smaller and look the token up in the other one
+    let [smaller, larger] = owned.len() <= seriesTokens.len() ? [owned, seriesTokens] : [seriesTokens, owned];
+    let count = 0;
+    let tokenIds = smaller.toArray();
+    for (let i = 0; i < tokenIds.length; i++) {
+        if (larger.contains(tokenIds[i])) {
+            count += 1;
+            if (stopAtFirst) {
+                break;
+            }
+        }
+    }
+    return count;
+}
+
+//check whether an account owns at least one token from any of the given series (for token gated access)
+export function internalOwnsAnyInSeries({
+    contract,
+    accountId,
+    ids
+}:{
+    contract: Contract,
+    accountId: string,
+    ids: number[]
+}): boolean {
+    assert(ids.length <= MAX_SERIES_CONFIGS_QUERY, `Cannot query more than ${MAX_SERIES_CONFIGS_QUERY} series at once`);
+    return ids.some(id => ownedInSeries(contract, accountId, id, true) > 0);
+}
+
+//get how many tokens of a series an account owns
+export function internalOwnsCountInSeries({
+    contract,
+    accountId,
+    id
+}:{
+    contract: Contract,
+    accountId: string,
+    id: number
+}): number {
+    return ownedInSeries(contract, accountId, id, false);
+}
@@ -0,0 +1,131 @@
+import { assert, bytes, near } from "near-sdk-js";
+import { Contract } from ".";
+import { assertContractOwner } from "./internal";
+import { Series } from "./metadata";
+import { internalMintBlockReason, internalMintToken, internalRecordAccountMint } from "./series";
+
+const GAS_FOR_FT_TRANSFER = 10_000_000_000_000;
+const GAS_FOR_STORAGE_DEPOSIT = 10_000_000_000_000;
+const GAS_FOR_RESOLVE_FT_REGISTRATION = 5_000_000_000_000;
+
+//handles fungible token payments for series priced in an FT. The msg needs to be JSON with the
+//series ID and the account that should receive the token: {"series_id": 0, "receiver_id": "bob.near"}
+export function internalFtOnTransfer({
+    contract,
+    senderId,
+    amount,
+    msg
+}:{
+    contract: Contract,
+    senderId: string,
+    amount: string,
+    msg: string
+}): string {
+    //the predecessor is the FT contract that is sending us the tokens
+    let ftContractId = near.predecessorAccountId();
+
+    let args = JSON.parse(msg);
+    let id = args.series_id as number;
+    let receiverId = args.receiver_id != null ? args.receiver_id as string : senderId;
+
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+
+    //make sure the series is priced in this FT and the exact price was sent
+    assert(series.ft_price != null, "Series is not priced in a fungible token");
+    assert(series.ft_price[0] == ftContractId, "Wrong fungible token for this series");
+    assert(BigInt(series.ft_price[1]) == BigInt(amount), `Must transfer exactly ${series.ft_price[1]} to mint`);
+
+    //make sure none of the minting gates block the sender
+    let blockReason = internalMintBlockReason(contract, id, series, senderId);
+    assert(blockReason == null, blockReason);
+
+    internalMintToken({ contract, id, series, receiverId });
+    internalRecordAccountMint(contract, id, series, senderId);
+
+    //forward the payment to the series owner
+    const promise = near.promiseBatchCreate(ftContractId);
+    near.promiseBatchActionFunctionCall(
+        promise, 
+        "ft_transfer", 
+        bytes(JSON.stringify({ 
+            receiver_id: series.owner_id,
+            amount: amount
+        })), 
+        1, // exactly 1 yoctoNEAR is required by ft_transfer
+        GAS_FOR_FT_TRANSFER
+    );
+
+    //we keep every token that was sent so none of them will be refunded
+    return "0";
+}
+//register the contract for storage on an FT contract so ft_transfer_call payments into it don't fail.
+//The attached deposit is forwarded to storage_deposit and the FT is tracked once the registration succeeds
+export function internalRegisterForFt({
+    contract,
+    ftAccountId
+}:{
+    contract: Contract,
+    ftAccountId: string
+}) {
+    //only the contract owner can spend the contract's deposit on FT registrations
+    assertContractOwner(contract);
+    let deposit = near.attachedDeposit().valueOf();
+    assert(deposit > BigInt(0), "Must attach a deposit to cover the FT storage registration");
+
+    const promise = near.promiseBatchCreate(ftAccountId);
+    near.promiseBatchActionFunctionCall(
+        promise, 
+        "storage_deposit", 
+        bytes(JSON.stringify({ 
+            account_id: near.currentAccountId(),
+            registration_only: true
+        })), 
+        deposit,
+        GAS_FOR_STORAGE_DEPOSIT
+    );
+
+    //track the FT once the registration went through
+    near.promiseThen(
+        promise, 
+        near.currentAccountId(), 
+        "resolve_ft_registration", 
+        bytes(JSON.stringify({
+            ft_account_id: ftAccountId
+        })), 
+        0, // no deposit 
+        GAS_FOR_RESOLVE_FT_REGISTRATION
+    );
+    return near.promiseReturn(promise);
+}
+
+//resolves the storage_deposit call made by register_for_ft and returns whether the registration succeeded
+export function internalResolveFtRegistration({
+    contract,
+    ftAccountId
+}:{
+    contract: Contract,
+    ftAccountId: string
+}): boolean {
+    assert(near.currentAccountId() === near.predecessorAccountId(), "Only the contract itself can call this method");
+    let result = near.promiseResult(0);
+    if (typeof result !== 'string') {
+        return false;
+    }
+    contract.registeredFts.set(ftAccountId);
+    return true;
+}
+
+//check whether the contract has registered for storage on an FT contract through register_for_ft
+export function internalIsRegisteredForFt({
+    contract,
+    ftAccountId
+}:{
+    contract: Contract,
+    ftAccountId: string
+}): boolean {
+    return contract.registeredFts.contains(ftAccountId);
+}
@@ -1,11 +1,14 @@
 import { NearContract, NearBindgen, near, call, view, LookupMap, UnorderedMap, Vector, UnorderedSet, LookupSet } from 'near-sdk-js'
-import { NFTContractMetadata, Token, TokenMetadata, internalNftMetadata } from './metadata';
-import { internalCreateSeries, internalMint, internalUpdateSeriesId } from './series';
-import { internalNftSupplyForSeries, internalNftTokens, internalNftTokensForSeries, internalSeries, internalSeriesInfo, internalSupplyForOwner, internalSupplySeries, internalTokensForOwner, internalTotalSupply } from './enumeration';
-import { internalNftToken, internalNftTransfer, internalNftTransferCall, internalResolveTransfer } from './nft_core';
-import { internalNftApprove, internalNftIsApproved, internalNftRevoke, internalNftRevokeAll } from './approval';
-import { internalNftPayout, internalNftTransferPayout } from './royalty';
-import { internalAddApprovedCreator, internalAddApprovedMinters, internalIsApprovedCreator, internalIsApprovedMinter, internalRemoveApprovedCreator, internalRemoveApprovedMinters } from './owner';
+import { NFTContractMetadata, Token, TokenMetadata, internalNftMetadata, internalSupportedInterfaces } from './metadata';
+import { internalAddToSeriesAllowlist, internalCreateSeries, internalCreateSeriesBatch, internalDeleteSeries, internalEffectiveMintPrice, internalGetSeriesManagers, internalIsMaterialized, internalIsOnSeriesAllowlist, internalLazyAvailable, internalLockSeries, internalMergeSeries, internalMint, internalMintBatch, internalMintPrecheck, internalNextClaimableEditions, internalReassignCreatorRoyalty, internalRemainingMintsForAccount, internalRefundMint, internalReleaseMintProceeds, internalRemoveFromSeriesAllowlist, internalRepairSeriesMembership, internalRevealSeries, internalRewriteMediaPrefix, internalSeriesIsLocked, internalSeriesIsTransferable, internalSeriesIsMintedOut, internalSeriesMaxSupply, internalSeriesMintWindow, internalSeriesPrice, internalSetBuiltinMarketEnabled, internalSetDiscountCode, internalSetEnforceRoyaltyOnTransfer, internalSetSeriesManager, internalSetSeriesMerkleRoot, internalSetSeriesPresale, internalSetSeriesTags, internalSetSoulbound, internalSetTokenMetadata, internalTransferSeries, internalUpdateSeriesId, internalUpdateSeriesRoyalty } from './series';
+import { internalNftContractStats, internalNftSupplyForSeries, internalNftTokens, internalNftTokensForSeries, internalOwnsAnyInSeries, internalOwnsCountInSeries, internalSeries, internalSeriesByTag, internalSeriesConfigs, internalSeriesHolders, internalSeriesInfo, internalSeriesRemainingSupply, internalSeriesTotalMinted, internalSupplyForOwner, internalSupplySeries, internalTokensForOwner, internalTokensHeldLongerThan, internalTokensSince, internalTotalBurned, internalTotalSupply } from './enumeration';
+import { internalExpireToken, internalNftBatchTransfer, internalNftBurn, internalNftDistribute, internalNftToken, internalTokenEventSummary, internalTokenOwner, internalTokenState, internalNftTransfer, internalNftTransferCall, internalResolveTransfer } from './nft_core';
+import { internalNftApprovals, internalNftApprovalExpiresAt, internalNftApprove, internalNftApproveBatch, internalNftIsApproved, internalNftRevoke, internalNftRevokeAll, internalTokenApprovalHistory } from './approval';
+import { internalClaimRoyalties, internalNftPayout, internalRoyaltiesOwed, internalNftTransferPayout } from './royalty';
+import { internalFtOnTransfer, internalIsRegisteredForFt, internalRegisterForFt, internalResolveFtRegistration } from './ft';
+import { internalAcceptOffer, internalCancelAuction, internalMakeOffer, internalPlaceBid, internalSetAutoAcceptPrice, internalSettleAuction, internalStartAuction, internalTokenAuction, internalTokenOffers, internalWithdrawOffer } from './market';
+import { internalStorageBalanceBounds, internalStorageBalanceOf, internalStorageDeposit, internalStorageUnregister, internalStorageWithdraw } from './storage';
+import { internalAddAllowedMediaPrefix, internalAddApprovedCreator, internalAddApprovedMinters, internalAddRoyaltyExemptAccount, internalContractConfig, internalGetAdminLog, internalGetAllowedMediaPrefixes, internalGetApprovedCreators, internalGetApprovedMinters, internalIsApprovedCreator, internalIsApprovedMinter, internalIsRoyaltyExemptAccount, internalMigrate, internalMigrateApprovedAccounts, internalRemoveAllowedMediaPrefix, internalRemoveApprovedCreator, internalRemoveApprovedMinters, internalRemoveRoyaltyExemptAccount, internalAcceptOwnership, internalCancelOwnershipProposal, internalProposeOwner, internalSetAccumulateRoyalties, internalSetBaseUri, internalSetContractIcon, internalSetContractName, internalSetDefaultRoyalty, internalSetEmitEvents, internalSetMarketFee, internalSetMaxRoyaltyBps, internalSetMediaPolicy, internalSetPaused, internalSetPlatformFee, internalSetPlatformRoyalty, internalSetRefundWindow, internalUpdateContractMetadata } from './owner';
 
 /// This spec can be treated like a version of the standard.
 export const NFT_METADATA_SPEC = "nft-1.0.0";
@@ -13,6 +16,9 @@ export const NFT_METADATA_SPEC = "nft-1.0.0";
 /// This is the name of the NFT standard we're using
 export const NFT_STANDARD_NAME = "nep171";
 
+/// Version of the contract state layout. Bump it whenever a field is added to the contract so migrate can fill it in
+export const CONTRACT_STATE_VERSION = 1;
+
 @NearBindgen
 export class Contract extends NearContract {
     owner_id: string;
@@ -22,6 +28,47 @@ export class Contract extends NearContract {
     tokensById: UnorderedMap;
     tokensPerOwner: LookupMap;
     metadata: NFTContractMetadata;
+    adminLog: Vector;
+    platformRoyaltyAccount: string;
+    platformRoyaltyBps: number;
+    mediaPolicyEnabled: boolean;
+    allowDataUris: boolean;
+    allowedMediaPrefixes: string[];
+    approvalHistory: LookupMap;
+    royaltyExemptAccounts: LookupSet;
+    mintLog: Vector;
+    auctionsByTokenId: LookupMap;
+    marketFeeRecipient: string;
+    marketFeeBps: number;
+    offersByTokenId: LookupMap;
+    autoAcceptByTokenId: LookupMap;
+    accumulateRoyalties: boolean;
+    royaltiesOwed: LookupMap;
+    emitEvents: boolean;
+    seriesByTag: LookupMap;
+    tokenEvents: LookupMap;
+    registeredFts: LookupSet;
+    maxRoyaltyBps: number;
+    mintsPerAccount: LookupMap;
+    seriesAllowlist: LookupSet;
+    defaultRoyalty: { [accountId: string]: number };
+    pendingOwner: string;
+    rolesEnumerable: boolean;
+    approvedMintersList: UnorderedSet;
+    approvedCreatorsList: UnorderedSet;
+    paused: boolean;
+    storageBalances: LookupMap;
+    stateVersion: number;
+    ownerCount: number;
+    tokenMetadataOverrides: LookupMap;
+    treasuryId: string;
+    platformFeeBps: number;
+    totalSupply: number;
+    totalBurned: number;
+    discountCodes: LookupMap;
+    refundableMints: LookupMap;
+    refundWindowNs: string;
+    nextSeriesKey: number;
 
     /*
         initialization function (can only be called once).
@@ -44,6 +91,48 @@ export class Contract extends NearContract {
         this.tokensPerOwner = new LookupMap("tokensPerOwner");
         this.tokensById = new UnorderedMap("tokensById");
         this.metadata = metadata;
+        this.adminLog = new Vector("adminLog");
+        this.platformRoyaltyAccount = owner_id;
+        this.platformRoyaltyBps = 0;
+        this.mediaPolicyEnabled = false;
+        this.allowDataUris = false;
+        this.allowedMediaPrefixes = [];
+        this.approvalHistory = new LookupMap("approvalHistory");
+        this.royaltyExemptAccounts = new LookupSet("royaltyExemptAccounts");
+        this.mintLog = new Vector("mintLog");
+        this.auctionsByTokenId = new LookupMap("auctionsByTokenId");
+        this.marketFeeRecipient = owner_id;
+        this.marketFeeBps = 0;
+        this.offersByTokenId = new LookupMap("offersByTokenId");
+        this.autoAcceptByTokenId = new LookupMap("autoAcceptByTokenId");
+        this.accumulateRoyalties = false;
+        this.royaltiesOwed = new LookupMap("royaltiesOwed");
+        this.emitEvents = true;
+        this.seriesByTag = new LookupMap("seriesByTag");
+        this.tokenEvents = new LookupMap("tokenEvents");
+        this.registeredFts = new LookupSet("registeredFts");
+        this.maxRoyaltyBps = 10000;
+        this.mintsPerAccount = new LookupMap("mintsPerAccount");
+        this.seriesAllowlist = new LookupSet("seriesAllowlist");
+        this.defaultRoyalty = {};
+        this.pendingOwner = "";
+        this.rolesEnumerable = true;
+        this.approvedMintersList = new UnorderedSet("approvedMintersList");
+        this.approvedCreatorsList = new UnorderedSet("approvedCreatorsList");
+        this.paused = false;
+        this.storageBalances = new LookupMap("storageBalances");
+        this.stateVersion = CONTRACT_STATE_VERSION;
+        this.ownerCount = 0;
+        this.tokenMetadataOverrides = new LookupMap("tokenMetadataOverrides");
+        this.treasuryId = "";
+        this.platformFeeBps = 0;
+        this.totalSupply = 0;
+        this.totalBurned = 0;
+        this.discountCodes = new LookupMap("discountCodes");
+        this.refundableMints = new LookupMap("refundableMints");
+        //paid mints of refundable series can be returned for 10 minutes by default
+        this.refundWindowNs = "600000000000";
+        this.nextSeriesKey = 0;
     }
 
     default() {
@@ -54,13 +143,87 @@ export class Contract extends NearContract {
         SERIES
     */
     @call
-    create_series({ id, metadata, royalty }) {
-        return internalCreateSeries({ contract: this, id: id, metadata: metadata, royalty: royalty });
+    create_series({ id, metadata, royalty, opt_out_platform_royalty, ft_price, expires_at, lazy, tags, price, mint_start, mint_end, mint_limit_per_account, placeholder_metadata, metadata_pool, transferable, max_supply, refundable }) {
+        return internalCreateSeries({ contract: this, id: id, metadata: metadata, royalty: royalty, optOutPlatformRoyalty: opt_out_platform_royalty, ftPrice: ft_price, expiresAt: expires_at, lazy: lazy, tags: tags, price: price, mintStart: mint_start, mintEnd: mint_end, mintLimitPerAccount: mint_limit_per_account, placeholderMetadata: placeholder_metadata, metadataPool: metadata_pool, transferable: transferable, maxSupply: max_supply, refundable: refundable });
+    }
+
+    @call
+    //create several series in one call. The attached deposit needs to cover the storage of all of them
+    create_series_batch({ series }) {
+        return internalCreateSeriesBatch({ contract: this, series: series });
+    }
+
+    @call
+    //mint the next token of a series. Priced series can be minted by anyone, the deposit needs to cover the price and storage
+    //An optional discount code takes a percentage off the price
+    nft_mint({ id, receiver_id, code }) {
+        return internalMint({ contract: this, id: id, receiverId: receiver_id, code: code });
+    }
+
+    @call
+    //mint from a series with a merkle allowlist. The proof is a list of hex encoded sibling hashes for the caller's account ID
+    nft_mint_with_proof({ id, receiver_id, proof, code }) {
+        return internalMint({ contract: this, id: id, receiverId: receiver_id, proof: proof, code: code });
+    }
+
+    @call
+    //airdrop one token of a series to each receiver (approved minters only). The deposit needs to cover the storage of every token
+    nft_mint_batch({ id, receiver_ids }) {
+        return internalMintBatch({ contract: this, id: id, receiverIds: receiver_ids });
+    }
+
+    @view
+    //check whether an account could mint from a series right now, and the first reason why not
+    mint_precheck({ id, account_id }) {
+        return internalMintPrecheck({ contract: this, id: id, accountId: account_id });
     }
 
     @call
-    nft_mint({ id, receiver_id }) {
-        return internalMint({ contract: this, id: id, receiverId: receiver_id });
+    //return a paid mint of a refundable series within the refund window. Burns the token and refunds the price minus a small fee
+    refund_mint({ token_id }) {
+        return internalRefundMint({ contract: this, tokenId: token_id });
+    }
+
+    @call
+    //pay out the held price of a refundable mint once it can't be refunded anymore. Anyone can call it
+    release_mint_proceeds({ token_id }) {
+        return internalReleaseMintProceeds({ contract: this, tokenId: token_id });
+    }
+
+    @call
+    //add or replace a discount code of a series under the hex encoded sha256 hash of the code (series owner only). 0 uses removes it
+    set_discount_code({ id, code_hash, percent_off, max_uses }) {
+        return internalSetDiscountCode({ contract: this, id: id, codeHash: code_hash, percentOff: percent_off, maxUses: max_uses });
+    }
+
+    @view
+    //quote the full cost of an account minting from a series to itself: the price, the discount of an optional code, the platform fee and the storage
+    effective_mint_price({ id, account_id, code }) {
+        return internalEffectiveMintPrice({ contract: this, id: id, accountId: account_id, code: code });
+    }
+
+    @view
+    //get the price (in yoctoNEAR) of minting from a series
+    series_price({ id }) {
+        return internalSeriesPrice({ contract: this, id: id });
+    }
+
+    @view
+    //get when a series opens and closes for minting, along with the current block timestamp
+    series_mint_window({ id }) {
+        return internalSeriesMintWindow({ contract: this, id: id });
+    }
+
+    @view
+    //get how many more tokens an account can mint from a series (null if there's no per-account limit)
+    remaining_mints_for_account({ id, account_id }) {
+        return internalRemainingMintsForAccount({ contract: this, id: id, accountId: account_id });
+    }
+
+    @view
+    //check whether an account is on the allowlist of a series
+    is_on_series_allowlist({ id, account_id }) {
+        return internalIsOnSeriesAllowlist({ contract: this, id: id, accountId: account_id });
     }
 
     @call
@@ -68,6 +231,195 @@ export class Contract extends NearContract {
         return internalUpdateSeriesId({ contract: this, currentId: current_id, newId: new_id });
     }
 
+    @call
+    //move every token of one series into another series and delete the source (owner only)
+    merge_series({ from_series_id, into_series_id }) {
+        return internalMergeSeries({ contract: this, fromSeriesId: from_series_id, intoSeriesId: into_series_id });
+    }
+
+    @call
+    //replace a media / reference URI prefix on a series and emit metadata updates for a page of its tokens
+    rewrite_media_prefix({ id, old_prefix, new_prefix, from_index, limit }) {
+        return internalRewriteMediaPrefix({ contract: this, id: id, oldPrefix: old_prefix, newPrefix: new_prefix, fromIndex: from_index, limit: limit });
+    }
+
+    @call
+    //override some metadata fields of a single token (series owner or metadata manager, until the series is locked)
+    set_token_metadata({ token_id, metadata }) {
+        return internalSetTokenMetadata({ contract: this, tokenId: token_id, metadata: metadata });
+    }
+
+    @call
+    //grant an account manager permissions on a series (1 = mint, 2 = edit metadata, 4 = edit royalty, 8 = manage allowlist). 0 removes the manager
+    set_series_manager({ id, account_id, perms }) {
+        return internalSetSeriesManager({ contract: this, id: id, accountId: account_id, perms: perms });
+    }
+
+    @view
+    //get the managers of a series and their permission bits
+    get_series_managers({ id }) {
+        return internalGetSeriesManagers({ contract: this, id: id });
+    }
+
+    @view
+    //get how many editions of a lazy series can still be minted
+    lazy_available({ id }) {
+        return internalLazyAvailable({ contract: this, id: id });
+    }
+
+    @view
+    //check whether an edition of a lazy series has been minted
+    is_materialized({ id, edition }) {
+        return internalIsMaterialized({ contract: this, id: id, edition: edition });
+    }
+
+    @view
+    //get the editions of a lazy series the next mints will receive, in order
+    next_claimable_editions({ id, n }) {
+        return internalNextClaimableEditions({ contract: this, id: id, n: n });
+    }
+
+    @view
+    //check whether a series has reached its supply cap
+    series_is_minted_out({ id }) {
+        return internalSeriesIsMintedOut({ contract: this, id: id });
+    }
+
+    @view
+    //check whether a series has been locked against minting
+    series_is_locked({ id }) {
+        return internalSeriesIsLocked({ contract: this, id: id });
+    }
+
+    @view
+    //check whether the tokens of a series can be transferred (false for soulbound series)
+    series_is_transferable({ id }) {
+        return internalSeriesIsTransferable({ contract: this, id: id });
+    }
+
+    @view
+    //get the hard cap on the number of tokens a series can mint (null if only its copies apply)
+    series_max_supply({ id }) {
+        return internalSeriesMaxSupply({ contract: this, id: id });
+    }
+
+    @call
+    //make a series soulbound (non-transferable) or transferable again while it has no tokens
+    set_soulbound({ id, soulbound }) {
+        return internalSetSoulbound({ contract: this, id: id, soulbound: soulbound });
+    }
+
+    @call
+    //block plain transfers of a series' tokens so sales have to go through nft_transfer_payout
+    set_enforce_royalty_on_transfer({ id, enforce }) {
+        return internalSetEnforceRoyaltyOnTransfer({ contract: this, id: id, enforce: enforce });
+    }
+
+    @call
+    //move a creator's royalty share to a new account across a page of your series
+    reassign_creator_royalty({ old_account_id, new_account_id, from_index, limit }) {
+        return internalReassignCreatorRoyalty({ contract: this, oldAccountId: old_account_id, newAccountId: new_account_id, fromIndex: from_index, limit: limit });
+    }
+
+    @call
+    //replace the discovery tags of a series
+    set_series_tags({ id, tags }) {
+        return internalSetSeriesTags({ contract: this, id: id, tags: tags });
+    }
+
+    @call
+    //delete a series that has no tokens and get its storage refunded
+    delete_series({ id }) {
+        return internalDeleteSeries({ contract: this, id: id });
+    }
+
+    @call
+    //hand a series to another account
+    transfer_series({ id, new_owner_id }) {
+        return internalTransferSeries({ contract: this, id: id, newOwnerId: new_owner_id });
+    }
+
+    @call
+    //permanently stop a series from minting, optionally freezing its royalty as well
+    lock_series({ id, freeze_royalty }) {
+        return internalLockSeries({ contract: this, id: id, freezeRoyalty: freeze_royalty });
+    }
+
+    @call
+    //replace the royalty of a series
+    update_series_royalty({ id, royalty }) {
+        return internalUpdateSeriesRoyalty({ contract: this, id: id, royalty: royalty });
+    }
+
+    @call
+    //reveal the real metadata of a series created with placeholder metadata
+    reveal_series({ id }) {
+        return internalRevealSeries({ contract: this, id: id });
+    }
+
+    @call
+    //allow or block auctions and offers on the built-in marketplace for a series' tokens
+    set_builtin_market_enabled({ id, enabled }) {
+        return internalSetBuiltinMarketEnabled({ contract: this, id: id, enabled: enabled });
+    }
+
+    @call
+    //add accounts to the allowlist of a series
+    add_to_series_allowlist({ id, account_ids }) {
+        return internalAddToSeriesAllowlist({ contract: this, id: id, accountIds: account_ids });
+    }
+
+    @call
+    //remove accounts from the allowlist of a series
+    remove_from_series_allowlist({ id, account_ids }) {
+        return internalRemoveFromSeriesAllowlist({ contract: this, id: id, accountIds: account_ids });
+    }
+
+    @call
+    //start or end the presale of a series. Only allowlisted accounts can mint while it's active
+    set_series_presale({ id, active }) {
+        return internalSetSeriesPresale({ contract: this, id: id, active: active });
+    }
+
+    @call
+    //set or clear the merkle root of a series' allowlist, used by nft_mint_with_proof
+    set_series_merkle_root({ id, root }) {
+        return internalSetSeriesMerkleRoot({ contract: this, id: id, root: root });
+    }
+
+    @call
+    //add missing tokens to a series' token set and drop entries that no longer exist, a page at a time (owner only)
+    repair_series_membership({ id, from_index, limit }) {
+        return internalRepairSeriesMembership({ contract: this, id: id, fromIndex: from_index, limit: limit });
+    }
+
+    /*
+        FT PAYMENTS
+    */
+    @call
+    //mint a token from a series that is priced in a fungible token. Called by the FT contract through ft_transfer_call
+    ft_on_transfer({ sender_id, amount, msg }) {
+        return internalFtOnTransfer({ contract: this, senderId: sender_id, amount: amount, msg: msg });
+    }
+
+    @call
+    //register the contract for storage on an FT contract with the attached deposit (owner only)
+    register_for_ft({ ft_account_id }) {
+        return internalRegisterForFt({ contract: this, ftAccountId: ft_account_id });
+    }
+
+    @call
+    //resolves the storage_deposit call made by register_for_ft
+    resolve_ft_registration({ ft_account_id }) {
+        return internalResolveFtRegistration({ contract: this, ftAccountId: ft_account_id });
+    }
+
+    @view
+    //check whether the contract has registered for storage on an FT contract
+    is_registered_for_ft({ ft_account_id }) {
+        return internalIsRegisteredForFt({ contract: this, ftAccountId: ft_account_id });
+    }
+
     /*
         CORE
     */
@@ -77,10 +429,40 @@ export class Contract extends NearContract {
         return internalNftToken({ contract: this, tokenId: token_id });
     }
 
+    @view
+    //get only the owner of a token (null if it doesn't exist). Cheaper than nft_token
+    token_owner({ token_id }) {
+        return internalTokenOwner({ contract: this, tokenId: token_id });
+    }
+
+    @view
+    //get ownership, metadata, royalty, restrictions, auction and offers for a token in one call
+    token_state({ token_id }) {
+        return internalTokenState({ contract: this, tokenId: token_id });
+    }
+
+    @view
+    //get the most recent mint, transfer, burn and approval events of a token with their block timestamps
+    token_event_summary({ token_id }) {
+        return internalTokenEventSummary({ contract: this, tokenId: token_id });
+    }
+
     @call
     //implementation of the nft_transfer method. This transfers the NFT from the current owner to the receiver. 
-    nft_transfer({ receiver_id, token_id, approval_id, memo }) {
-        return internalNftTransfer({ contract: this, receiverId: receiver_id, tokenId: token_id, approvalId: approval_id, memo: memo });
+    nft_transfer({ receiver_id, token_id, approval_id, memo, retain_approval_for }) {
+        return internalNftTransfer({ contract: this, receiverId: receiver_id, tokenId: token_id, approvalId: approval_id, memo: memo, retainApprovalFor: retain_approval_for });
+    }
+
+    @call
+    //transfer several tokens in a single call, each as [receiver_id, token_id, approval_id, memo]
+    nft_batch_transfer({ transfers }) {
+        return internalNftBatchTransfer({ contract: this, transfers: transfers });
+    }
+
+    @call
+    //burn a token owned by (or approved to) the caller
+    nft_burn({ token_id, memo }) {
+        return internalNftBurn({ contract: this, tokenId: token_id, memo: memo });
     }
 
     @call
@@ -89,6 +471,12 @@ export class Contract extends NearContract {
         return internalNftTransferCall({ contract: this, receiverId: receiver_id, tokenId: token_id, approvalId: approval_id, memo: memo, msg: msg });
     }
 
+    @call
+    //move already minted tokens owned by the caller to a list of recipients in one call (approved minters only)
+    nft_distribute({ distributions }) {
+        return internalNftDistribute({ contract: this, distributions: distributions });
+    }
+
     @call
     //resolves the cross contract call when calling nft_on_transfer in the nft_transfer_call method
     //returns true if the token was successfully transferred to the receiver_id
@@ -96,6 +484,12 @@ export class Contract extends NearContract {
         return internalResolveTransfer({ contract: this, authorizedId: authorized_id, ownerId: owner_id, receiverId: receiver_id, tokenId: token_id, approvedAccountIds: approved_account_ids, memo: memo });
     }
 
+    @call
+    //burn a token whose series has expired. Anyone can call this after the expiry
+    expire_token({ token_id }) {
+        return internalExpireToken({ contract: this, tokenId: token_id });
+    }
+
     /*
         APPROVALS
     */
@@ -107,8 +501,26 @@ export class Contract extends NearContract {
 
     @call
     //approve an account ID to transfer a token on your behalf
-    nft_approve({ token_id, account_id, msg }) {
-        return internalNftApprove({ contract: this, tokenId: token_id, accountId: account_id, msg: msg });
+    nft_approve({ token_id, account_id, msg, expires_at }) {
+        return internalNftApprove({ contract: this, tokenId: token_id, accountId: account_id, msg: msg, expiresAt: expires_at });
+    }
+
+    @call
+    //approve several accounts on a token at once, returns the approval IDs in the same order
+    nft_approve_batch({ token_id, account_ids, msg }) {
+        return internalNftApproveBatch({ contract: this, tokenId: token_id, accountIds: account_ids, msg: msg });
+    }
+
+    @view
+    //get every approved account on a token and its approval ID
+    nft_approvals({ token_id }) {
+        return internalNftApprovals({ contract: this, tokenId: token_id });
+    }
+
+    @view
+    //get the block timestamp an account's approval on a token expires at, null if it never expires
+    nft_approval_expires_at({ token_id, account_id }) {
+        return internalNftApprovalExpiresAt({ contract: this, tokenId: token_id, accountId: account_id });
     }
 
     /*
@@ -126,6 +538,18 @@ export class Contract extends NearContract {
         return internalNftTransferPayout({ contract: this, receiverId: receiver_id, tokenId: token_id, approvalId: approval_id, memo: memo, balance: balance, maxLenPayout: max_len_payout });
     }
 
+    @call
+    //withdraw every royalty the contract has accumulated for you in one transfer
+    claim_royalties() {
+        return internalClaimRoyalties({ contract: this });
+    }
+
+    @view
+    //get the royalties waiting to be claimed by an account
+    royalties_owed({ account_id }) {
+        return internalRoyaltiesOwed({ contract: this, accountId: account_id });
+    }
+
     @call
     //approve an account ID to transfer a token on your behalf
     nft_revoke({ token_id, account_id }) {
@@ -138,6 +562,75 @@ export class Contract extends NearContract {
         return internalNftRevokeAll({ contract: this, tokenId: token_id });
     }
 
+    @view
+    //get the most recent approvals and revocations for a token
+    token_approval_history({ token_id }) {
+        return internalTokenApprovalHistory({ contract: this, tokenId: token_id });
+    }
+
+    /*
+        MARKET
+    */
+    @call
+    //put a token you own up for auction. The token is held by the contract until the auction is settled
+    start_auction({ token_id, reserve, end_at, min_bid_increment_bps, extension_window }) {
+        return internalStartAuction({ contract: this, tokenId: token_id, reserve: reserve, endAt: end_at, minBidIncrementBps: min_bid_increment_bps, extensionWindow: extension_window });
+    }
+
+    @call
+    //bid the attached deposit on a running auction
+    place_bid({ token_id }) {
+        return internalPlaceBid({ contract: this, tokenId: token_id });
+    }
+
+    @call
+    //settle an auction after its end and pay out the winning bid
+    settle_auction({ token_id }) {
+        return internalSettleAuction({ contract: this, tokenId: token_id });
+    }
+
+    @call
+    //cancel your auction and get the token back. Only possible before the first bid
+    cancel_auction({ token_id }) {
+        return internalCancelAuction({ contract: this, tokenId: token_id });
+    }
+
+    @view
+    //get the live state of a token's auction (null if there's no auction)
+    token_auction({ token_id }) {
+        return internalTokenAuction({ contract: this, tokenId: token_id });
+    }
+
+    @call
+    //offer the attached deposit for a token. A first offer also pays for its storage out of the deposit
+    make_offer({ token_id }) {
+        return internalMakeOffer({ contract: this, tokenId: token_id });
+    }
+
+    @call
+    //accept a buyer's offer on your token
+    accept_offer({ token_id, buyer_id }) {
+        return internalAcceptOffer({ contract: this, tokenId: token_id, buyerId: buyer_id });
+    }
+
+    @call
+    //withdraw your offer on a token and get the deposit back
+    withdraw_offer({ token_id }) {
+        return internalWithdrawOffer({ contract: this, tokenId: token_id });
+    }
+
+    @call
+    //automatically accept offers on your token at or above the price (null removes it)
+    set_auto_accept_price({ token_id, price }) {
+        return internalSetAutoAcceptPrice({ contract: this, tokenId: token_id, price: price });
+    }
+
+    @view
+    //get every standing offer on a token, highest first
+    token_offers({ token_id }) {
+        return internalTokenOffers({ contract: this, tokenId: token_id });
+    }
+
     /*
         ENUMERATION
     */
@@ -147,16 +640,34 @@ export class Contract extends NearContract {
         return internalTotalSupply({ contract: this });
     }
 
+    @view
+    //Query for the number of NFTs that have been burned on the contract
+    nft_total_burned() {
+        return internalTotalBurned({ contract: this });
+    }
+
+    @view
+    //get the number of series, tokens and distinct owners on the contract
+    nft_contract_stats() {
+        return internalNftContractStats({ contract: this });
+    }
+
     @view
     //Query for nft tokens on the contract regardless of the owner using pagination
-    nft_tokens({ from_index, limit }) {
-        return internalNftTokens({ contract: this, fromIndex: from_index, limit: limit });
+    nft_tokens({ from_index, limit, reverse }) {
+        return internalNftTokens({ contract: this, fromIndex: from_index, limit: limit, reverse: reverse });
     }
 
     @view
-    //get the total supply of NFTs for a given owner
-    nft_tokens_for_owner({ account_id, from_index, limit }) {
-        return internalTokensForOwner({ contract: this, accountId: account_id, fromIndex: from_index, limit: limit });
+    //get the tokens minted since a mint log index and the next index to resume syncing from
+    tokens_since({ since_log_index, limit }) {
+        return internalTokensSince({ contract: this, sinceLogIndex: since_log_index, limit: limit });
+    }
+
+    @view
+    //get the tokens for a given owner, optionally only the ones from the series with the passed in series_id
+    nft_tokens_for_owner({ account_id, from_index, limit, series_id, reverse }) {
+        return internalTokensForOwner({ contract: this, accountId: account_id, fromIndex: from_index, limit: limit, seriesId: series_id, reverse: reverse });
     }
 
     @view
@@ -165,6 +676,12 @@ export class Contract extends NearContract {
         return internalSupplyForOwner({ contract: this, accountId: account_id });
     }
 
+    @view
+    //get the tokens an owner has held for longer than the passed in duration (in nanoseconds)
+    tokens_held_longer_than({ account_id, duration }) {
+        return internalTokensHeldLongerThan({ contract: this, accountId: account_id, duration: duration });
+    }
+
     @view
     //Query for the total number of series 
     get_supply_series() {
@@ -183,16 +700,91 @@ export class Contract extends NearContract {
         return internalSeriesInfo({ contract: this, id: id });
     }
 
+    @view
+    // get info for a list of series, returning null for any unknown ID
+    series_configs({ ids }) {
+        return internalSeriesConfigs({ contract: this, ids: ids });
+    }
+
     @view
     //get the total supply of NFTs for a series
     nft_supply_for_series({ id }) {
         return internalNftSupplyForSeries({ contract: this, id: id });
     }
 
+    @view
+    //get how many more tokens a capped series can mint (null for uncapped series)
+    series_remaining_supply({ id }) {
+        return internalSeriesRemainingSupply({ contract: this, id: id });
+    }
+
+    @view
+    //get how many tokens a series currently has
+    series_total_minted({ id }) {
+        return internalSeriesTotalMinted({ contract: this, id: id });
+    }
+
     @view
     //Paginate through NFTs for a specific series
-    nft_tokens_for_series({ id, from_index, limit }) {
-        return internalNftTokensForSeries({ contract: this, id: id, fromIndex: from_index, limit: limit });
+    nft_tokens_for_series({ id, from_index, limit, reverse }) {
+        return internalNftTokensForSeries({ contract: this, id: id, fromIndex: from_index, limit: limit, reverse: reverse });
+    }
+
+    @view
+    //Paginate through the holders of a series along with how many of its tokens they own
+    series_holders({ id, from_index, limit }) {
+        return internalSeriesHolders({ contract: this, id: id, fromIndex: from_index, limit: limit });
+    }
+
+    @view
+    //paginate through the series that have a tag
+    series_by_tag({ tag, from_index, limit }) {
+        return internalSeriesByTag({ contract: this, tag: tag, fromIndex: from_index, limit: limit });
+    }
+
+    @view
+    //check whether an account owns at least one token from any of the passed in series
+    owns_any_in_series({ account_id, ids }) {
+        return internalOwnsAnyInSeries({ contract: this, accountId: account_id, ids: ids });
+    }
+
+    @view
+    //get how many tokens of a series an account owns
+    owns_count_in_series({ account_id, id }) {
+        return internalOwnsCountInSeries({ contract: this, accountId: account_id, id: id });
+    }
+
+    /*
+        STORAGE
+    */
+    @call
+    //pre-fund storage for an account (the caller by default) so it can mint without attaching a storage deposit
+    storage_deposit({ account_id, registration_only }) {
+        return internalStorageDeposit({ contract: this, accountId: account_id, registrationOnly: registration_only });
+    }
+
+    @call
+    //withdraw storage funds that aren't locked for the registration and the tokens the caller holds
+    storage_withdraw({ amount }) {
+        return internalStorageWithdraw({ contract: this, amount: amount });
+    }
+
+    @call
+    //unregister the caller and refund their storage balance. Not possible while holding tokens
+    storage_unregister({ force }) {
+        return internalStorageUnregister({ contract: this, force: force });
+    }
+
+    @view
+    //get the storage balance of an account, null if it isn't registered
+    storage_balance_of({ account_id }) {
+        return internalStorageBalanceOf({ contract: this, accountId: account_id });
+    }
+
+    @view
+    //get the minimum balance needed to register and hold a token
+    storage_balance_bounds() {
+        return internalStorageBalanceBounds();
     }
 
     /*
@@ -204,6 +796,36 @@ export class Contract extends NearContract {
         return internalNftMetadata({ contract: this });
     }
 
+    @call
+    //replace the contract metadata (owner only)
+    update_contract_metadata({ metadata }) {
+        return internalUpdateContractMetadata({ contract: this, metadata: metadata });
+    }
+
+    @call
+    //set the contract name (owner only)
+    set_contract_name({ name }) {
+        return internalSetContractName({ contract: this, name: name });
+    }
+
+    @call
+    //set the contract icon (owner only)
+    set_contract_icon({ icon }) {
+        return internalSetContractIcon({ contract: this, icon: icon });
+    }
+
+    @call
+    //set the base URI token media is resolved against (owner only)
+    set_base_uri({ base_uri }) {
+        return internalSetBaseUri({ contract: this, baseUri: base_uri });
+    }
+
+    @view
+    //get the identifiers of every standard and extension the contract implements
+    supported_interfaces() {
+        return internalSupportedInterfaces({ contract: this });
+    }
+
     /*
         OWNER
     */
@@ -236,4 +858,155 @@ export class Contract extends NearContract {
     is_approved_creator({ account_id }) {
         return internalIsApprovedCreator({ contract: this, accountId: account_id });
     }
-}
\ No newline at end of file
+
+    @view
+    //paginate through the approved minters
+    get_approved_minters({ from_index, limit }) {
+        return internalGetApprovedMinters({ contract: this, fromIndex: from_index, limit: limit });
+    }
+
+    @view
+    //paginate through the approved creators
+    get_approved_creators({ from_index, limit }) {
+        return internalGetApprovedCreators({ contract: this, fromIndex: from_index, limit: limit });
+    }
+
+    @call
+    //copy approved minters and creators added by older deployments into the enumerable lists (owner only). Pass complete with the last page
+    migrate_approved_accounts({ minters, creators, complete }) {
+        return internalMigrateApprovedAccounts({ contract: this, minters: minters, creators: creators, complete: complete });
+    }
+
+    @call
+    //exempt an account (e.g. a trusted marketplace) from royalty enforcement on plain transfers
+    add_royalty_exempt_account({ account_id }) {
+        return internalAddRoyaltyExemptAccount({ contract: this, accountId: account_id });
+    }
+
+    @call
+    remove_royalty_exempt_account({ account_id }) {
+        return internalRemoveRoyaltyExemptAccount({ contract: this, accountId: account_id });
+    }
+
+    @view
+    is_royalty_exempt_account({ account_id }) {
+        return internalIsRoyaltyExemptAccount({ contract: this, accountId: account_id });
+    }
+
+    @call
+    //set the fee taken from sales on the built-in marketplace (owner only)
+    set_market_fee({ account_id, bps }) {
+        return internalSetMarketFee({ contract: this, accountId: account_id, bps: bps });
+    }
+
+    @call
+    //set how long (in nanoseconds) paid mints of refundable series can be refunded for (owner only)
+    set_refund_window({ window_ns }) {
+        return internalSetRefundWindow({ contract: this, windowNs: window_ns });
+    }
+
+    @call
+    //set the treasury and the share of paid mints it receives (owner only)
+    set_platform_fee({ treasury_id, platform_fee_bps }) {
+        return internalSetPlatformFee({ contract: this, treasuryId: treasury_id, platformFeeBps: platform_fee_bps });
+    }
+
+    @call
+    //cap the total royalty paid out for any series (owner only)
+    set_max_royalty_bps({ bps }) {
+        return internalSetMaxRoyaltyBps({ contract: this, bps: bps });
+    }
+
+    @call
+    //set the royalty new series start with when they're created without one (owner only)
+    set_default_royalty({ royalty }) {
+        return internalSetDefaultRoyalty({ contract: this, royalty: royalty });
+    }
+
+    @call
+    //propose a new contract owner, who has to accept before the ownership changes (owner only)
+    propose_owner({ new_owner_id }) {
+        return internalProposeOwner({ contract: this, newOwnerId: new_owner_id });
+    }
+
+    @call
+    //accept a pending ownership proposal (pending owner only)
+    accept_ownership() {
+        return internalAcceptOwnership({ contract: this });
+    }
+
+    @call
+    //cancel a pending ownership proposal (owner only)
+    cancel_ownership_proposal() {
+        return internalCancelOwnershipProposal({ contract: this });
+    }
+
+    @call
+    //set the platform royalty that is injected into every new series (owner only)
+    set_platform_royalty({ account_id, bps }) {
+        return internalSetPlatformRoyalty({ contract: this, accountId: account_id, bps: bps });
+    }
+
+    @call
+    //turn the storage gateway policy for media / reference URIs on or off (owner only)
+    set_media_policy({ enabled, allow_data_uris }) {
+        return internalSetMediaPolicy({ contract: this, enabled: enabled, allowDataUris: allow_data_uris });
+    }
+
+    @call
+    //credit built-in marketplace royalties for claim_royalties instead of sending them on every sale (owner only)
+    set_accumulate_royalties({ enabled }) {
+        return internalSetAccumulateRoyalties({ contract: this, enabled: enabled });
+    }
+
+    @call
+    //turn mint, transfer and burn events on or off for bulk operations (owner only)
+    set_emit_events({ enabled }) {
+        return internalSetEmitEvents({ contract: this, enabled: enabled });
+    }
+
+    @call
+    //pause or unpause every state-changing method on the contract (owner only)
+    set_paused({ paused }) {
+        return internalSetPaused({ contract: this, paused: paused });
+    }
+
+    @call
+    //allow media / reference URIs that start with the given prefix (owner only)
+    add_allowed_media_prefix({ prefix }) {
+        return internalAddAllowedMediaPrefix({ contract: this, prefix: prefix });
+    }
+
+    @call
+    //stop allowing media / reference URIs that start with the given prefix (owner only)
+    remove_allowed_media_prefix({ prefix }) {
+        return internalRemoveAllowedMediaPrefix({ contract: this, prefix: prefix });
+    }
+
+    @view
+    //get the list of allowed media / reference URI prefixes
+    get_allowed_media_prefixes() {
+        return internalGetAllowedMediaPrefixes({ contract: this });
+    }
+
+    @view
+    //get all of the contract's governance settings in one call
+    contract_config() {
+        return internalContractConfig({ contract: this });
+    }
+
+    @view
+    //paginate through the history of owner actions
+    get_admin_log({ from_index, limit }) {
+        return internalGetAdminLog({ contract: this, fromIndex: from_index, limit: limit });
+    }
+}
+
+/*
+    fill in fields added since the deployed state was written. Only the contract itself can call this.
+    Like init, this is exported outside the contract class so it runs without deserializing the old state first
+*/
+export function migrate() {
+    let version = internalMigrate();
+    near.valueReturn(JSON.stringify(version));
+}
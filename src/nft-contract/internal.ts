@@ -1,6 +1,11 @@
 import { assert, near, UnorderedSet, Vector } from "near-sdk-js";
 import { Contract, NFT_METADATA_SPEC, NFT_STANDARD_NAME } from ".";
-import { Token } from "./metadata";
+import { AdminAction, ApprovalEvent, Series, Token, TokenMetadata } from "./metadata";
+
+//maximum number of approval events kept per token. Older events are dropped as new ones come in.
+export const MAX_APPROVAL_HISTORY = 20;
+//maximum number of entries kept in a token's event summary. Older entries are dropped as new ones come in.
+export const MAX_TOKEN_EVENTS = 20;
 
 // Gets a collection and deserializes it into a set that can be used.
 export function restoreOwners(collection) {
@@ -57,12 +62,262 @@ export function refundDeposit(storageUsed: bigint) {
     }
 }
 
+//append an owner action to the admin log. The log is append-only and grows by one entry per
+//owner-gated mutation, so its storage is paid for by the contract account.
+export function internalRecordAdminAction(contract: Contract, description: string) {
+    contract.adminLog.push(new AdminAction({
+        timestamp: near.blockTimestamp().toString(),
+        actor: near.predecessorAccountId(),
+        description
+    }));
+}
+
+//append an approve / revoke event to a token's rolling approval history
+export function internalRecordApprovalEvent(contract: Contract, tokenId: string, action: string, accountId: string, approvalId?: number) {
+    let history = contract.approvalHistory.get(tokenId) as ApprovalEvent[];
+    if (history == null) {
+        history = [];
+    }
+
+    history.push(new ApprovalEvent({
+        timestamp: near.blockTimestamp().toString(),
+        action,
+        accountId,
+        approvalId
+    }));
+
+    //only keep the most recent events to bound the storage used per token
+    if (history.length > MAX_APPROVAL_HISTORY) {
+        history = history.slice(history.length - MAX_APPROVAL_HISTORY);
+    }
+    contract.approvalHistory.set(tokenId, history);
+    internalRecordTokenEvent(contract, tokenId, action);
+}
+
+//append a [block timestamp, event type] entry to a token's rolling event summary
+export function internalRecordTokenEvent(contract: Contract, tokenId: string, eventType: string) {
+    let events = contract.tokenEvents.get(tokenId) as [string, string][];
+    if (events == null) {
+        events = [];
+    }
+    events.push([near.blockTimestamp().toString(), eventType]);
+
+    //only keep the most recent entries to bound the storage used per token
+    if (events.length > MAX_TOKEN_EVENTS) {
+        events = events.slice(events.length - MAX_TOKEN_EVENTS);
+    }
+    contract.tokenEvents.set(tokenId, events);
+}
+
+//when the media policy is enabled, make sure every media / reference URI points at an allowed storage gateway
+export function assertAllowedMediaUris(contract: Contract, metadata: TokenMetadata) {
+    if (!contract.mediaPolicyEnabled || metadata == null) {
+        return;
+    }
+
+    [metadata.media, metadata.reference].forEach(uri => {
+        if (uri == null) {
+            return;
+        }
+        //data URIs are allowed or denied separately from the gateway prefixes
+        if (uri.startsWith("data:")) {
+            assert(contract.allowDataUris, "Data URIs are not allowed");
+            return;
+        }
+        let allowed = contract.allowedMediaPrefixes.some(prefix => uri.startsWith(prefix));
+        assert(allowed, `URI ${uri} does not use an allowed storage gateway`);
+    });
+}
+
+//substitute the {edition} and {series_id} placeholders in a templated series metadata field
+export function fillMetadataTemplate(value: string, seriesId: number, editionNumber: string): string {
+    if (value == null) {
+        return value;
+    }
+    return value.split("{edition}").join(editionNumber).split("{series_id}").join(seriesId.toString());
+}
+
+//check whether the tokens of a series have expired
+export function isSeriesExpired(series: Series): boolean {
+    return series.expires_at != null && near.blockTimestamp().valueOf() >= BigInt(series.expires_at);
+}
+
+//get how many tokens a series can ever hold. An explicit max_supply governs, series without one fall back to their copies
+export function seriesSupplyCap(series: Series): number {
+    if (series.max_supply != null) {
+        return series.max_supply;
+    }
+    return series.metadata.copies != null ? series.metadata.copies : null;
+}
+
+//check whether a series has reached its supply cap or handed out its whole metadata pool. Series without either are uncapped
+export function isSeriesMintedOut(series: Series): boolean {
+    //series handing out metadata from a pool are sold out once the pool is empty
+    if (series.metadata_pool != null && series.metadata_pool.length == 0) {
+        return true;
+    }
+    let cap = seriesSupplyCap(series);
+    if (cap == null) {
+        return false;
+    }
+    let tokens = UnorderedSet.deserialize(series.tokens as UnorderedSet);
+    return tokens.len() >= cap;
+}
+
+//check whether the tokens of a series can be transferred. Series created before the flag existed are transferable
+export function isSeriesTransferable(series: Series): boolean {
+    return series.transferable != false;
+}
+
+/*
+    for series that enforce royalties, block plain transfers (nft_transfer / nft_transfer_call) unless the sender
+    or receiver is exempt. Exempt accounts are the contract itself, the series owner and any account the contract
+    owner added to the royalty exempt set (e.g. trusted marketplaces). Value transfers need to go through
+    nft_transfer_payout so the royalty is computed. There is no gift threshold since plain transfers carry no price.
+*/
+export function assertPlainTransferAllowed(contract: Contract, tokenId: string, senderId: string, receiverId: string) {
+    let token = contract.tokensById.get(tokenId) as Token;
+    if (token == null) {
+        return;
+    }
+    // @ts-ignore
+    let series = contract.seriesById.get(token.series_id) as Series;
+    if (series == null || series.enforce_royalty_on_transfer != true) {
+        return;
+    }
+
+    let isExempt = (accountId: string) => accountId == near.currentAccountId() || accountId == series.owner_id || contract.royaltyExemptAccounts.contains(accountId);
+    assert(isExempt(senderId) || isExempt(receiverId) || isExempt(token.owner_id), "Series enforces royalties, use nft_transfer_payout");
+}
+
+//permanently remove a token from tokensById, its owner's set and its series' set
+export function internalBurnToken(contract: Contract, tokenId: string, memo?: string): Token {
+    assertNotPaused(contract);
+    let token = contract.tokensById.get(tokenId) as Token;
+    if (token == null) {
+        near.panic("no token found");
+    }
+
+    contract.tokensById.remove(tokenId);
+    contract.totalSupply = contract.totalSupply != null ? contract.totalSupply - 1 : contract.tokensById.len();
+    contract.totalBurned = (contract.totalBurned != null ? contract.totalBurned : 0) + 1;
+    contract.tokenMetadataOverrides.remove(tokenId);
+    internalRecordTokenEvent(contract, tokenId, "burn");
+    internalRemoveTokenFromOwner(contract, token.owner_id, tokenId);
+
+    // @ts-ignore
+    let series = contract.seriesById.get(token.series_id) as Series;
+    if (series != null) {
+        let tokens = UnorderedSet.deserialize(series.tokens as UnorderedSet);
+        tokens.remove(tokenId);
+        series.tokens = tokens;
+        // @ts-ignore
+        contract.seriesById.set(token.series_id, series);
+    }
+
+    // Construct the burn log as per the events standard.
+    let nftBurnLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The data related with the event stored in a vector.
+        event: "nft_burn",
+        data: [
+            {
+                // Owner of the token.
+                owner_id: token.owner_id,
+                // Vector of token IDs that were burned.
+                token_ids: [tokenId],
+                // An optional memo to include.
+                memo,
+            }
+        ]
+    }
+
+    // Log the json.
+    if (eventsEnabled(contract)) {
+        near.log(`EVENT_JSON:${JSON.stringify(nftBurnLog)}`);
+    }
+
+    //return the token that was burned
+    return token;
+}
+
+//get the edition number of a token. Tokens minted before editions were stored derive it from their token ID
+export function tokenEdition(tokenId: string, token: Token): number {
+    if (token.edition != null) {
+        return token.edition;
+    }
+    return parseInt(tokenId.split(":")[1]);
+}
+
+/*
+    check whether mint, transfer and burn events should be logged. The contract owner can turn them off for bulk
+    operations that would exceed the log limits. Indexers don't see the tokens moved while events are off, so they
+    need to resync those from an off-chain snapshot or the mint log. State saved before the flag existed logs events.
+*/
+export function eventsEnabled(contract: Contract): boolean {
+    return contract.emitEvents != false;
+}
+
+/*
+    build the token a lazy series would hold for an edition that hasn't been minted yet. Editions from the
+    series' next edition up to its supply cap are derived on read and nominally owned by the series owner, the
+    stored token is only created (materialized) when the edition is minted. Returns null for anything else.
+*/
+export function lazyUnmintedToken(contract: Contract, tokenId: string): Token {
+    let [seriesId, editionPart] = tokenId.split(":");
+    let edition = parseInt(editionPart);
+    if (editionPart == null || isNaN(edition) || edition.toString() != editionPart) {
+        return null;
+    }
+    // @ts-ignore
+    let series = contract.seriesById.get(parseInt(seriesId)) as Series;
+    if (series == null || series.lazy != true) {
+        return null;
+    }
+    let nextEdition = series.next_edition != null ? series.next_edition : 1;
+    if (edition < nextEdition || edition > seriesSupplyCap(series)) {
+        return null;
+    }
+    return new Token({
+        series_id: parseInt(seriesId),
+        owner_id: series.owner_id,
+        approved_account_ids: {},
+        next_approval_id: 0,
+        edition
+    });
+}
+
+//get the edition the next token of a series will receive and advance the series' edition counter
+export function nextSeriesEdition(series: Series): number {
+    //series created before the counter existed continue from their token count
+    let edition = series.next_edition != null ? series.next_edition : UnorderedSet.deserialize(series.tokens as UnorderedSet).len() + 1;
+    series.next_edition = edition + 1;
+    return edition;
+}
+
 //calculate how many bytes the account ID is taking up
 export function bytesForApprovedAccountId(accountId: string): number {
     // The extra 4 bytes are coming from Borsh serialization to store the length of the string.
     return accountId.length + 4 + 8;
 }
 
+//check whether an account's approval on a token has passed its expiry. Approvals without an expiry never expire
+export function isApprovalExpired(token: Token, accountId: string): boolean {
+    let expiresAt = token.approval_expires_at != null ? token.approval_expires_at[accountId] : null;
+    return expiresAt != null && near.blockTimestamp().valueOf() >= BigInt(expiresAt);
+}
+
+//get the approval ID of an account on a token, null if the account isn't approved or its approval expired
+export function activeApprovalId(token: Token, accountId: string): number {
+    if (!token.approved_account_ids.hasOwnProperty(accountId) || isApprovalExpired(token, accountId)) {
+        return null;
+    }
+    return token.approved_account_ids[accountId];
+}
+
 //Assert that the user has attached at least 1 yoctoNEAR (for security reasons and to pay for storage)
 export function assertAtLeastOneYocto() {
     assert(near.attachedDeposit().valueOf() >= BigInt(1), "Requires attached deposit of at least 1 yoctoNEAR");
@@ -73,6 +328,30 @@ export function assertOneYocto() {
     assert(near.attachedDeposit().toString() === "1", "Requires attached deposit of exactly 1 yoctoNEAR");
 }
 
+//check that an account ID is a valid NEAR account ID (2 to 64 characters of lowercase letters, digits and separators)
+export function isValidAccountId(accountId: string): boolean {
+    return accountId != null
+        && accountId.length >= 2
+        && accountId.length <= 64
+        && /^(([a-z\d]+[-_])*[a-z\d]+\.)*([a-z\d]+[-_])*[a-z\d]+$/.test(accountId);
+}
+
+//panic if the owner paused the contract. Every state-changing entry point (and the shared mint, transfer and
+//burn helpers) calls this, so new methods built on top of those helpers are covered automatically
+export function assertNotPaused(contract: Contract) {
+    assert(contract.paused != true, "Contract is paused");
+}
+
+//check whether an account is the contract owner. The contract account itself always counts as the owner
+export function isContractOwner(contract: Contract, accountId: string): boolean {
+    return accountId === contract.owner_id || accountId === near.currentAccountId();
+}
+
+//used to make sure that only the contract owner (or the contract account itself) can call owner methods
+export function assertContractOwner(contract: Contract) {
+    assert(isContractOwner(contract, near.predecessorAccountId()), "Only the contract owner can call this method");
+}
+
 //add a token to the set of tokens an owner has
 export function internalAddTokenToOwner(contract: Contract, accountId: string, tokenId: string) {
     //get the set of tokens for the given account
@@ -81,6 +360,8 @@ export function internalAddTokenToOwner(contract: Contract, accountId: string, t
     if(tokenSet == null) {
         //if the account doesn't have any tokens, we create a new unordered set
         tokenSet = new UnorderedSet("tokensPerOwner" + accountId.toString());
+        //the account just became an owner
+        contract.ownerCount = (contract.ownerCount != null ? contract.ownerCount : 0) + 1;
     }
 
     //we insert the token ID into the set
@@ -105,13 +386,21 @@ export function internalRemoveTokenFromOwner(contract: Contract, accountId: stri
     //if the token set is now empty, we remove the owner from the tokens_per_owner collection
     if (tokenSet.isEmpty()) {
         contract.tokensPerOwner.remove(accountId);
+        //the account no longer owns anything
+        if (contract.ownerCount != null && contract.ownerCount > 0) {
+            contract.ownerCount -= 1;
+        }
     } else { //if the token set is not empty, we simply insert it back for the account ID. 
         contract.tokensPerOwner.set(accountId, tokenSet);
     }
 }
 
 //transfers the NFT to the receiver_id (internal method and can't be called directly via CLI).
-export function internalTransfer(contract: Contract, senderId: string, receiverId: string, tokenId: string, approvalId: number, memo: string): Token {
+//batch transfers pass logEvent = false and emit a single grouped nft_transfer event themselves.
+//escrowRelease is set when the contract hands back a token it holds in escrow, which has to work even after the
+//series expired or turned soulbound so escrowed tokens (and the bids on them) never get stuck.
+export function internalTransfer(contract: Contract, senderId: string, receiverId: string, tokenId: string, approvalId: number, memo: string, logEvent: boolean = true, escrowRelease: boolean = false): Token {
+    assertNotPaused(contract);
     //get the token object by passing in the token_id
     let token = contract.tokensById.get(tokenId) as Token;
     if (token == null) {
@@ -120,8 +409,8 @@ export function internalTransfer(contract: Contract, senderId: string, receiverI
 
     //if the sender doesn't equal the owner, we check if the sender is in the approval list
     if (senderId != token.owner_id) {
-        //if the token's approved account IDs doesn't contain the sender, we panic
-        if (!token.approved_account_ids.hasOwnProperty(senderId)) {
+        //if the token's approved account IDs doesn't contain the sender (or their approval expired), we panic
+        if (activeApprovalId(token, senderId) == null) {
             near.panic("Unauthorized");
         }
 
@@ -139,6 +428,13 @@ export function internalTransfer(contract: Contract, senderId: string, receiverI
         }
     }
 
+    //expired tokens can't be transferred anymore
+    // @ts-ignore
+    let series = contract.seriesById.get(token.series_id) as Series;
+    assert(escrowRelease || series == null || !isSeriesExpired(series), "Token has expired");
+    //soulbound tokens can't be transferred
+    assert(escrowRelease || series == null || isSeriesTransferable(series), "Token is soulbound");
+
     //we make sure that the sender isn't sending the token to themselves
     assert(token.owner_id != receiverId, "The token owner and the receiver should be different")
 
@@ -152,19 +448,27 @@ export function internalTransfer(contract: Contract, senderId: string, receiverI
         series_id: token.series_id,
         owner_id: receiverId,
         //reset the approval account IDs
-        // @ts-ignore
-        approvedAccountIds: {},
-        nextApprovalId: token.next_approval_id,
+        approved_account_ids: {},
+        next_approval_id: token.next_approval_id,
+        edition: token.edition,
+        original_edition: token.original_edition,
+        last_transfer_at: near.blockTimestamp().toString(),
+        metadata: token.metadata,
     });
 
     //insert that new token into the tokens_by_id, replacing the old entry 
     contract.tokensById.set(tokenId, newToken);
+    internalRecordTokenEvent(contract, tokenId, "transfer");
 
     //if there was some memo attached, we log it. 
     if (memo != null) {
         near.log(`Memo: ${memo}`);
     }
 
+    if (!logEvent) {
+        return token
+    }
+
     // Default the authorized ID to be None for the logs.
     let authorizedId;
 
@@ -198,7 +502,9 @@ export function internalTransfer(contract: Contract, senderId: string, receiverI
     }
 
     // Log the serialized json.
-    near.log(JSON.stringify(nftTransferLog));
+    if (eventsEnabled(contract)) {
+        near.log(JSON.stringify(nftTransferLog));
+    }
 
     //return the previous token object that was transferred.
     return token
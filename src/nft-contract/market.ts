@@ -0,0 +1,502 @@
+import { assert, near } from "near-sdk-js";
+import { Contract, NFT_METADATA_SPEC, NFT_STANDARD_NAME } from ".";
+import { assertOneYocto, internalTransfer, isSeriesExpired, refundApprovedAccountIds, royaltyToPayout, assertNotPaused } from "./internal";
+import { Auction, AuctionState, Offer, Series, Token } from "./metadata";
+import { clampRoyalty, internalCreditRoyalty, royaltyPayoutFor } from "./royalty";
+
+//bids in the last 5 minutes of an auction extend it by 5 minutes unless the seller picks another window
+const DEFAULT_AUCTION_EXTENSION_WINDOW = "300000000000";
+
+//smallest offer (in yoctoNEAR, 0.01 NEAR) that can be made on a token, not counting the storage of the offer
+export const MIN_OFFER_AMOUNT = "10000000000000000000000";
+
+//how many buyers can have a standing offer on a single token
+export const MAX_OFFERS_PER_TOKEN = 50;
+
+//the storage (in yoctoNEAR) of one buyer's entry in a token's offers. The buyer pays it when the offer is made
+//and gets it back with the offer once the entry is removed
+function offerStorageCost(buyerId: string): bigint {
+    //the amount is stored as a string of at most 40 digits, plus the quotes, colon and comma around the entry
+    return BigInt(buyerId.length + 40 + 6) * near.storageByteCost().valueOf();
+}
+
+//send a buyer's escrowed offer back together with the storage they paid for it
+function refundOffer(buyerId: string, amount: string) {
+    const promise = near.promiseBatchCreate(buyerId);
+    near.promiseBatchActionTransfer(promise, BigInt(amount) + offerStorageCost(buyerId));
+}
+
+//send a payout object to every account in it
+export function internalSendPayout(payout: { [accountId: string]: string }) {
+    Object.entries(payout).forEach(([accountId, amount]) => {
+        if (BigInt(amount) > BigInt(0)) {
+            const promise = near.promiseBatchCreate(accountId);
+            near.promiseBatchActionTransfer(promise, BigInt(amount));
+        }
+    });
+}
+
+/*
+    split the price of a built-in marketplace sale. The market fee is taken first, the series royalty is then
+    computed on what remains and the seller receives the rest. Rounding dust goes to the seller so the
+    payout always sums to exactly the sale price.
+*/
+export function internalMarketSalePayout(contract: Contract, tokenId: string, sellerId: string, price: bigint): { [accountId: string]: string } {
+    let fee = contract.marketFeeBps > 0 ? BigInt(royaltyToPayout(contract.marketFeeBps, price)) : BigInt(0);
+    let remainder = price - fee;
+
+    let token = contract.tokensById.get(tokenId) as Token;
+    // @ts-ignore
+    let series = token != null ? contract.seriesById.get(token.series_id) as Series : null;
+    let royalty = series != null ? clampRoyalty(contract, series.royalty) : {};
+    let payout = royaltyPayoutFor(royalty, sellerId, remainder);
+
+    //give the seller whatever isn't paid to the royalty recipients
+    let paidToOthers = Object.entries(payout).filter(([accountId]) => accountId != sellerId).reduce((partialSum, [, amount]) => partialSum + BigInt(amount), BigInt(0));
+    payout[sellerId] = (remainder - paidToOthers).toString();
+
+    if (fee > BigInt(0)) {
+        let recipient = contract.marketFeeRecipient;
+        let existing = payout[recipient] != null ? BigInt(payout[recipient]) : BigInt(0);
+        payout[recipient] = (existing + fee).toString();
+    }
+    return payout;
+}
+
+/*
+    pay out a built-in marketplace sale. When the contract accumulates royalties, the series royalty recipients
+    are credited in royaltiesOwed (and withdraw with claim_royalties) instead of receiving a transfer for every sale.
+    The seller and the market fee recipient are always paid right away.
+*/
+export function internalSendSalePayout(contract: Contract, tokenId: string, sellerId: string, price: bigint) {
+    let payout = internalMarketSalePayout(contract, tokenId, sellerId, price);
+    if (contract.accumulateRoyalties) {
+        let token = contract.tokensById.get(tokenId) as Token;
+        // @ts-ignore
+        let series = token != null ? contract.seriesById.get(token.series_id) as Series : null;
+        let royalty = series != null ? series.royalty : {};
+        Object.keys(royalty).forEach(accountId => {
+            if (accountId != sellerId && accountId != contract.marketFeeRecipient && payout[accountId] != null) {
+                internalCreditRoyalty(contract, accountId, BigInt(payout[accountId]));
+                delete payout[accountId];
+            }
+        });
+    }
+    internalSendPayout(payout);
+}
+
+//make sure the creator of a token's series hasn't turned the built-in marketplace off. Series created before
+//the flag existed can use it
+function assertBuiltinMarketEnabled(contract: Contract, token: Token) {
+    // @ts-ignore
+    let series = contract.seriesById.get(token.series_id) as Series;
+    assert(series == null || series.builtin_market_enabled != false, "The built-in marketplace is disabled for this series");
+}
+
+//log a built-in marketplace event as per the events standard
+function logMarketEvent(event: string, data: object) {
+    let marketLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The name of the marketplace event.
+        event,
+        // The data related with the event stored in a vector.
+        data: [data]
+    }
+
+    // Log the json.
+    near.log(`EVENT_JSON:${JSON.stringify(marketLog)}`);
+}
+
+//put a token up for an ascending auction. The token is held by the contract until the auction is settled
+export function internalStartAuction({
+    contract,
+    tokenId,
+    reserve,
+    endAt,
+    minBidIncrementBps,
+    extensionWindow
+}:{
+    contract: Contract,
+    tokenId: string,
+    reserve: string,
+    endAt: string,
+    minBidIncrementBps?: number,
+    extensionWindow?: string
+}) {
+    assertNotPaused(contract);
+    //assert that the user attached exactly 1 yoctoNEAR for security reasons
+    assertOneYocto();
+    let sellerId = near.predecessorAccountId();
+
+    let token = contract.tokensById.get(tokenId) as Token;
+    if (token == null) {
+        near.panic("no token");
+    }
+    assert(token.owner_id == sellerId, "Only the token owner can start an auction");
+    assertBuiltinMarketEnabled(contract, token);
+    assert(BigInt(endAt) > near.blockTimestamp().valueOf(), "Auction must end in the future");
+
+    //escrow the token on the contract so it can't be moved while the auction is running
+    let previousToken = internalTransfer(contract, sellerId, near.currentAccountId(), tokenId, null, "auction escrow");
+    refundApprovedAccountIds(previousToken.owner_id, previousToken.approved_account_ids);
+
+    let increment = minBidIncrementBps != null ? minBidIncrementBps : 0;
+    assert(increment >= 0 && increment <= 10000, "Minimum bid increment must be between 0 and 10000 basis points");
+
+    contract.auctionsByTokenId.set(tokenId, new Auction({
+        sellerId,
+        reserve,
+        endAt,
+        minBidIncrementBps: increment,
+        extensionWindow: extensionWindow != null ? extensionWindow : DEFAULT_AUCTION_EXTENSION_WINDOW
+    }));
+
+    logMarketEvent("auction_start", { token_id: tokenId, seller_id: sellerId, reserve, end_at: endAt });
+}
+
+//place a bid on a running auction. The attached deposit is the bid and the previous high bidder is refunded
+export function internalPlaceBid({
+    contract,
+    tokenId
+}:{
+    contract: Contract,
+    tokenId: string
+}) {
+    assertNotPaused(contract);
+    let bidderId = near.predecessorAccountId();
+    let bid = near.attachedDeposit().valueOf();
+
+    let auction = contract.auctionsByTokenId.get(tokenId) as Auction;
+    if (auction == null) {
+        near.panic("no auction");
+    }
+    assert(near.blockTimestamp().valueOf() < BigInt(auction.end_at), "Auction has ended");
+    assert(bidderId != auction.seller_id, "Seller cannot bid on their own auction");
+    assert(bid >= BigInt(auction.reserve), `Bid must be at least the reserve of ${auction.reserve}`);
+    assert(auction.high_bid == null || bid > BigInt(auction.high_bid), `Bid must be higher than ${auction.high_bid}`);
+    //new bids need to beat the current high bid by at least the minimum increment
+    if (auction.high_bid != null && auction.min_bid_increment_bps > 0) {
+        let minBid = BigInt(auction.high_bid) * BigInt(10000 + auction.min_bid_increment_bps) / BigInt(10000);
+        assert(bid >= minBid, `Bid must be at least ${minBid}`);
+    }
+
+    //record the new high bid before refunding so the refund can't be replayed
+    let previousBidder = auction.high_bidder;
+    let previousBid = auction.high_bid;
+    auction.high_bidder = bidderId;
+    auction.high_bid = bid.toString();
+    //bids close to the end extend the auction so it can't be sniped
+    let window = BigInt(auction.extension_window != null ? auction.extension_window : 0);
+    if (BigInt(auction.end_at) - near.blockTimestamp().valueOf() < window) {
+        auction.end_at = (BigInt(auction.end_at) + window).toString();
+    }
+    contract.auctionsByTokenId.set(tokenId, auction);
+
+    if (previousBidder != null) {
+        const promise = near.promiseBatchCreate(previousBidder);
+        near.promiseBatchActionTransfer(promise, BigInt(previousBid));
+    }
+
+    logMarketEvent("auction_bid", { token_id: tokenId, bidder_id: bidderId, amount: auction.high_bid, end_at: auction.end_at });
+}
+
+//settle an auction after it ended. The token goes to the high bidder and the bid is split using the
+//series royalty, or the token goes back to the seller if nobody bid the reserve
+export function internalSettleAuction({
+    contract,
+    tokenId
+}:{
+    contract: Contract,
+    tokenId: string
+}) {
+    assertNotPaused(contract);
+    let auction = contract.auctionsByTokenId.get(tokenId) as Auction;
+    if (auction == null) {
+        near.panic("no auction");
+    }
+    assert(near.blockTimestamp().valueOf() >= BigInt(auction.end_at), "Auction has not ended");
+
+    //remove the auction before moving any funds
+    contract.auctionsByTokenId.remove(tokenId);
+
+    //bids are only accepted at or above the reserve, so no high bidder means the reserve wasn't met
+    if (auction.high_bidder == null) {
+        internalTransfer(contract, near.currentAccountId(), auction.seller_id, tokenId, null, "auction reserve not met", true, true);
+        logMarketEvent("auction_settle", { token_id: tokenId, seller_id: auction.seller_id, winner_id: null, amount: null });
+        return;
+    }
+
+    //the winner would only get a token that can't be transferred anymore if the series expired during the
+    //auction, so the bid is refunded and the token goes back to the seller instead
+    let token = contract.tokensById.get(tokenId) as Token;
+    // @ts-ignore
+    let series = contract.seriesById.get(token.series_id) as Series;
+    if (series != null && isSeriesExpired(series)) {
+        internalTransfer(contract, near.currentAccountId(), auction.seller_id, tokenId, null, "auction series expired", true, true);
+        const promise = near.promiseBatchCreate(auction.high_bidder);
+        near.promiseBatchActionTransfer(promise, BigInt(auction.high_bid));
+        logMarketEvent("auction_settle", { token_id: tokenId, seller_id: auction.seller_id, winner_id: null, amount: null });
+        return;
+    }
+
+    internalTransfer(contract, near.currentAccountId(), auction.high_bidder, tokenId, null, "auction settled", true, true);
+
+    //split the winning bid between the market fee, the royalty recipients and the seller
+    internalSendSalePayout(contract, tokenId, auction.seller_id, BigInt(auction.high_bid));
+
+    logMarketEvent("auction_settle", { token_id: tokenId, seller_id: auction.seller_id, winner_id: auction.high_bidder, amount: auction.high_bid });
+}
+
+//cancel an auction before anyone has bid on it and give the token back to the seller
+export function internalCancelAuction({
+    contract,
+    tokenId
+}:{
+    contract: Contract,
+    tokenId: string
+}) {
+    assertNotPaused(contract);
+    //assert that the user attached exactly 1 yoctoNEAR for security reasons
+    assertOneYocto();
+
+    let auction = contract.auctionsByTokenId.get(tokenId) as Auction;
+    if (auction == null) {
+        near.panic("no auction");
+    }
+    assert(near.predecessorAccountId() == auction.seller_id, "Only the seller can cancel the auction");
+    //once someone has bid, the auction has to run to the end to protect the bidders
+    assert(auction.high_bidder == null, "Cannot cancel an auction that has bids");
+
+    contract.auctionsByTokenId.remove(tokenId);
+    internalTransfer(contract, near.currentAccountId(), auction.seller_id, tokenId, null, "auction cancelled", true, true);
+
+    logMarketEvent("auction_cancel", { token_id: tokenId, seller_id: auction.seller_id });
+}
+
+//get the live state of a token's auction, or null if it isn't being auctioned
+export function internalTokenAuction({
+    contract,
+    tokenId
+}:{
+    contract: Contract,
+    tokenId: string
+}): AuctionState {
+    let auction = contract.auctionsByTokenId.get(tokenId) as Auction;
+    if (auction == null) {
+        return null;
+    }
+    return new AuctionState({
+        auction,
+        settleable: near.blockTimestamp().valueOf() >= BigInt(auction.end_at)
+    });
+}
+
+//make an offer on a token with the attached deposit. The deposit is held until the offer is accepted or withdrawn
+//and a new offer from the same buyer replaces (and refunds) their previous one. A buyer's first standing offer on
+//a token also pays for its storage out of the deposit, and every offer needs to be at least MIN_OFFER_AMOUNT
+export function internalMakeOffer({
+    contract,
+    tokenId
+}:{
+    contract: Contract,
+    tokenId: string
+}) {
+    assertNotPaused(contract);
+    let buyerId = near.predecessorAccountId();
+    let deposit = near.attachedDeposit().valueOf();
+    assert(deposit > BigInt(0), "Must attach a deposit to make an offer");
+
+    let token = contract.tokensById.get(tokenId) as Token;
+    if (token == null) {
+        near.panic("no token");
+    }
+    assert(token.owner_id != buyerId, "Cannot make an offer on your own token");
+    assertBuiltinMarketEnabled(contract, token);
+    assert(contract.auctionsByTokenId.get(tokenId) == null, "Token is being auctioned");
+
+    let offers = contract.offersByTokenId.get(tokenId) as { [buyerId: string]: string };
+    if (offers == null) {
+        offers = {};
+    }
+    let previousAmount = offers[buyerId];
+
+    //offers at or above the owner's auto accept price are executed right away instead of being queued
+    let autoAccept = contract.autoAcceptByTokenId.get(tokenId) as [string, string];
+    let executeNow = autoAccept != null && autoAccept[0] == token.owner_id && deposit >= BigInt(autoAccept[1]);
+    let amount = deposit;
+    if (executeNow) {
+        delete offers[buyerId];
+    } else {
+        //only a new entry takes up storage, a replaced offer keeps the storage that was already paid for
+        if (previousAmount == null) {
+            assert(Object.keys(offers).length < MAX_OFFERS_PER_TOKEN, `Tokens can't have more than ${MAX_OFFERS_PER_TOKEN} offers`);
+            let storageCost = offerStorageCost(buyerId);
+            assert(deposit > storageCost, `Must attach more than the ${storageCost} yoctoNEAR the offer's storage costs`);
+            amount = deposit - storageCost;
+        }
+        assert(amount >= BigInt(MIN_OFFER_AMOUNT), `Offers need to be at least ${MIN_OFFER_AMOUNT} yoctoNEAR`);
+        offers[buyerId] = amount.toString();
+    }
+    contract.offersByTokenId.set(tokenId, offers);
+
+    //refund the buyer's previous offer after the new one is recorded. Its storage is only given back once the entry is gone
+    if (previousAmount != null) {
+        const promise = near.promiseBatchCreate(buyerId);
+        near.promiseBatchActionTransfer(promise, executeNow ? BigInt(previousAmount) + offerStorageCost(buyerId) : BigInt(previousAmount));
+    }
+
+    logMarketEvent("offer_make", { token_id: tokenId, buyer_id: buyerId, amount: amount.toString() });
+
+    if (executeNow) {
+        internalExecuteOfferSale(contract, tokenId, token.owner_id, buyerId, amount.toString());
+    }
+}
+
+//accept a buyer's offer on a token you own. The token is transferred and the offer is paid out with the royalty split
+export function internalAcceptOffer({
+    contract,
+    tokenId,
+    buyerId
+}:{
+    contract: Contract,
+    tokenId: string,
+    buyerId: string
+}) {
+    assertNotPaused(contract);
+    //assert that the user attached exactly 1 yoctoNEAR for security reasons
+    assertOneYocto();
+    let sellerId = near.predecessorAccountId();
+
+    let offers = contract.offersByTokenId.get(tokenId) as { [buyerId: string]: string };
+    if (offers == null || offers[buyerId] == null) {
+        near.panic("no offer");
+    }
+    let amount = offers[buyerId];
+
+    //remove the offer before moving any funds
+    delete offers[buyerId];
+    contract.offersByTokenId.set(tokenId, offers);
+
+    internalExecuteOfferSale(contract, tokenId, sellerId, buyerId, amount);
+
+    //the buyer gets back the storage their offer took up
+    const promise = near.promiseBatchCreate(buyerId);
+    near.promiseBatchActionTransfer(promise, offerStorageCost(buyerId));
+}
+
+//transfer a token to a buyer whose escrowed offer was accepted and pay the offer out with the royalty split
+function internalExecuteOfferSale(contract: Contract, tokenId: string, sellerId: string, buyerId: string, amount: string) {
+    let token = contract.tokensById.get(tokenId) as Token;
+    if (token == null) {
+        near.panic("no token");
+    }
+    assertBuiltinMarketEnabled(contract, token);
+    //the sale pays whoever accepts, so approved accounts can't accept offers on the owner's behalf
+    assert(token.owner_id == sellerId, "Only the token owner can accept offers");
+
+    let previousToken = internalTransfer(contract, sellerId, buyerId, tokenId, null, "offer accepted");
+    refundApprovedAccountIds(previousToken.owner_id, previousToken.approved_account_ids);
+
+    //the auto accept price belonged to the previous owner
+    contract.autoAcceptByTokenId.remove(tokenId);
+
+    internalSendSalePayout(contract, tokenId, sellerId, BigInt(amount));
+
+    logMarketEvent("offer_accept", { token_id: tokenId, seller_id: sellerId, buyer_id: buyerId, amount });
+}
+
+//withdraw your offer on a token and get the deposit back
+export function internalWithdrawOffer({
+    contract,
+    tokenId
+}:{
+    contract: Contract,
+    tokenId: string
+}) {
+    assertNotPaused(contract);
+    //assert that the user attached exactly 1 yoctoNEAR for security reasons
+    assertOneYocto();
+    let buyerId = near.predecessorAccountId();
+
+    let offers = contract.offersByTokenId.get(tokenId) as { [buyerId: string]: string };
+    if (offers == null || offers[buyerId] == null) {
+        near.panic("no offer");
+    }
+    let amount = offers[buyerId];
+
+    //remove the offer before refunding it
+    delete offers[buyerId];
+    contract.offersByTokenId.set(tokenId, offers);
+
+    refundOffer(buyerId, amount);
+
+    logMarketEvent("offer_withdraw", { token_id: tokenId, buyer_id: buyerId, amount });
+}
+
+//refund and remove every standing offer on a token, e.g. once the token no longer exists
+export function internalRefundOffers(contract: Contract, tokenId: string) {
+    let offers = contract.offersByTokenId.get(tokenId) as { [buyerId: string]: string };
+    if (offers == null) {
+        return;
+    }
+    contract.offersByTokenId.remove(tokenId);
+
+    let buyers = Object.keys(offers);
+    for (let i = 0; i < buyers.length; i++) {
+        refundOffer(buyers[i], offers[buyers[i]]);
+        logMarketEvent("offer_withdraw", { token_id: tokenId, buyer_id: buyers[i], amount: offers[buyers[i]] });
+    }
+}
+
+//get every standing offer on a token, highest first
+export function internalTokenOffers({
+    contract,
+    tokenId
+}:{
+    contract: Contract,
+    tokenId: string
+}): Offer[] {
+    let offers = contract.offersByTokenId.get(tokenId) as { [buyerId: string]: string };
+    if (offers == null) {
+        return [];
+    }
+    return Object.entries(offers)
+        .map(([buyerId, amount]) => new Offer({ buyerId, amount }))
+        .sort((a, b) => {
+            let diff = BigInt(b.amount) - BigInt(a.amount);
+            return diff > BigInt(0) ? 1 : diff < BigInt(0) ? -1 : 0;
+        });
+}
+
+//set the price at or above which offers on your token are accepted automatically. Passing null removes it.
+//The price is tied to the current owner so it doesn't carry over to whoever owns the token next
+export function internalSetAutoAcceptPrice({
+    contract,
+    tokenId,
+    price
+}:{
+    contract: Contract,
+    tokenId: string,
+    price?: string
+}) {
+    assertNotPaused(contract);
+    //assert that the user attached exactly 1 yoctoNEAR for security reasons
+    assertOneYocto();
+    let ownerId = near.predecessorAccountId();
+
+    let token = contract.tokensById.get(tokenId) as Token;
+    if (token == null) {
+        near.panic("no token");
+    }
+    assert(token.owner_id == ownerId, "Only the token owner can set the auto accept price");
+
+    if (price == null) {
+        contract.autoAcceptByTokenId.remove(tokenId);
+    } else {
+        assert(BigInt(price) > BigInt(0), "Auto accept price must be greater than 0");
+        contract.autoAcceptByTokenId.set(tokenId, [ownerId, price]);
+    }
+}
@@ -108,22 +108,47 @@ export class Token {
     owner_id: string;
     approved_account_ids: { [accountId: string]: number };
     next_approval_id: number;
+    edition?: number;
+    original_edition?: number;
+    last_transfer_at?: string;
+    metadata?: TokenMetadata;
+    approval_expires_at?: { [accountId: string]: string };
 
     constructor({
         series_id,
         owner_id,
         approved_account_ids,
-        next_approval_id
+        next_approval_id,
+        edition,
+        original_edition,
+        last_transfer_at,
+        metadata,
+        approval_expires_at
     }:{
         series_id: number,
         owner_id: string,
         approved_account_ids: { [accountId: string]: number },
-        next_approval_id: number
+        next_approval_id: number,
+        edition?: number,
+        original_edition?: number,
+        last_transfer_at?: string,
+        metadata?: TokenMetadata,
+        approval_expires_at?: { [accountId: string]: string }
     }) {
         this.series_id = series_id
         this.owner_id = owner_id
         this.approved_account_ids = approved_account_ids
         this.next_approval_id = next_approval_id
+        //edition number within the current series
+        this.edition = edition
+        //edition number in the series the token was originally minted in, if it was merged into another series
+        this.original_edition = original_edition
+        //block timestamp (in nanoseconds) when the token was minted or last changed owner
+        this.last_transfer_at = last_transfer_at
+        //metadata assigned to this token at mint (e.g. from a metadata pool), used instead of the series metadata
+        this.metadata = metadata
+        //block timestamps (in nanoseconds) after which approvals stop counting. Approvals without one never expire
+        this.approval_expires_at = approval_expires_at
     }
 }
 
@@ -171,17 +196,20 @@ export class JsonSeries {
     metadata: TokenMetadata;
     royalty: { [accountId: string]: number };
     owner_id: string;
+    tags?: string[];
 
     constructor({
         seriesId,
         metadata,
         ownerId,
-        royalty
+        royalty,
+        tags
     }: {
         seriesId: number,
         metadata: TokenMetadata,
         ownerId: string,
-        royalty: { [accountId: string]: number }
+        royalty: { [accountId: string]: number },
+        tags?: string[]
     } ) {
         //series ID
         this.series_id = seriesId,
@@ -190,7 +218,53 @@ export class JsonSeries {
         //owner of the token
         this.owner_id = ownerId,
         //keep track of the royalty percentages for the token in a hash map
-        this.royalty = royalty
+        this.royalty = royalty,
+        //discovery tags of the series
+        this.tags = tags
+    }
+}
+
+//The arguments for one series in a create_series_batch call.
+export class SeriesCreateArgs {
+    id: number;
+    metadata: TokenMetadata;
+    royalty: { [accountId: string]: number };
+    price?: string;
+    opt_out_platform_royalty?: boolean;
+    transferable?: boolean;
+    max_supply?: number;
+
+    constructor({
+        id,
+        metadata,
+        royalty,
+        price,
+        optOutPlatformRoyalty,
+        transferable,
+        maxSupply
+    }: {
+        id: number,
+        metadata: TokenMetadata,
+        royalty: { [accountId: string]: number },
+        price?: string,
+        optOutPlatformRoyalty?: boolean,
+        transferable?: boolean,
+        maxSupply?: number
+    }) {
+        //ID of the series to create
+        this.id = id,
+        //token metadata
+        this.metadata = metadata,
+        //royalty percentages of the series
+        this.royalty = royalty,
+        //optional price (in yoctoNEAR) of a mint
+        this.price = price,
+        //whether the series is created without the platform royalty
+        this.opt_out_platform_royalty = optOutPlatformRoyalty,
+        //whether the series' tokens can be transferred, false makes the series soulbound
+        this.transferable = transferable,
+        //optional hard cap on the number of tokens, governs over metadata.copies
+        this.max_supply = maxSupply
     }
 }
 
@@ -200,17 +274,92 @@ export class Series {
     royalty: { [accountId: string]: number };
     owner_id: string;
     tokens: UnorderedSet;
+    opt_out_platform_royalty: boolean;
+    ft_price?: [string, string];
+    next_edition: number;
+    expires_at?: string;
+    managers: { [accountId: string]: number };
+    transferable: boolean;
+    enforce_royalty_on_transfer: boolean;
+    lazy: boolean;
+    tags?: string[];
+    price?: string;
+    locked: boolean;
+    builtin_market_enabled: boolean;
+    mint_start?: string;
+    mint_end?: string;
+    mint_limit_per_account?: number;
+    presale_active?: boolean;
+    placeholder_metadata?: TokenMetadata;
+    revealed: boolean;
+    metadata_pool?: TokenMetadata[];
+    royalty_frozen?: boolean;
+    allowlist_merkle_root?: string;
+    max_supply?: number;
+    refundable?: boolean;
+    closed_emitted?: boolean;
+    account_key?: string;
 
     constructor({
         metadata,
         ownerId,
         royalty,
-        tokens
+        tokens,
+        optOutPlatformRoyalty,
+        ftPrice,
+        nextEdition,
+        expiresAt,
+        managers,
+        transferable,
+        enforceRoyaltyOnTransfer,
+        lazy,
+        tags,
+        price,
+        locked,
+        builtinMarketEnabled,
+        mintStart,
+        mintEnd,
+        mintLimitPerAccount,
+        presaleActive,
+        placeholderMetadata,
+        revealed,
+        metadataPool,
+        royaltyFrozen,
+        allowlistMerkleRoot,
+        maxSupply,
+        refundable,
+        closedEmitted,
+        accountKey
     }: {
         metadata: TokenMetadata,
         ownerId: string,
         royalty: { [accountId: string]: number },
-        tokens: UnorderedSet
+        tokens: UnorderedSet,
+        optOutPlatformRoyalty: boolean,
+        ftPrice?: [string, string],
+        nextEdition: number,
+        expiresAt?: string,
+        managers: { [accountId: string]: number },
+        transferable: boolean,
+        enforceRoyaltyOnTransfer: boolean,
+        lazy: boolean,
+        tags?: string[],
+        price?: string,
+        locked: boolean,
+        builtinMarketEnabled: boolean,
+        mintStart?: string,
+        mintEnd?: string,
+        mintLimitPerAccount?: number,
+        presaleActive?: boolean,
+        placeholderMetadata?: TokenMetadata,
+        revealed: boolean,
+        metadataPool?: TokenMetadata[],
+        royaltyFrozen?: boolean,
+        allowlistMerkleRoot?: string,
+        maxSupply?: number,
+        refundable?: boolean,
+        closedEmitted?: boolean,
+        accountKey?: string
     } ) {
         //token metadata
         this.metadata = metadata,
@@ -219,7 +368,526 @@ export class Series {
         //keep track of the royalty percentages for the token in a hash map
         this.royalty = royalty,
         //list of tokens in the series
-        this.tokens = tokens
+        this.tokens = tokens,
+        //whether the series was created without the platform royalty
+        this.opt_out_platform_royalty = optOutPlatformRoyalty,
+        //optional price of a mint paid in a fungible token: [token contract, amount]
+        this.ft_price = ftPrice,
+        //edition number that will be given to the next token minted or merged into the series
+        this.next_edition = nextEdition,
+        //optional block timestamp (in nanoseconds) after which the series' tokens expire
+        this.expires_at = expiresAt,
+        //accounts that can manage the series, mapped to their permission bits
+        this.managers = managers,
+        //soulbound series set this to false so their tokens can't be transferred
+        this.transferable = transferable,
+        //when true, plain transfers between non-exempt accounts are blocked so sales go through nft_transfer_payout
+        this.enforce_royalty_on_transfer = enforceRoyaltyOnTransfer,
+        //lazy series only store a token once its edition is minted, unminted editions are derived on read
+        this.lazy = lazy,
+        //optional discovery tags, kept in the seriesByTag index
+        this.tags = tags,
+        //optional price (in yoctoNEAR) anyone can pay to mint a token of the series, storage is charged on top
+        this.price = price,
+        //locked series can never mint again, locking can't be undone
+        this.locked = locked,
+        //when false, the series' tokens can't be auctioned or sold through offers on the built-in marketplace
+        this.builtin_market_enabled = builtinMarketEnabled,
+        //optional block timestamp (in nanoseconds) minting opens at
+        this.mint_start = mintStart,
+        //optional block timestamp (in nanoseconds) minting closes at
+        this.mint_end = mintEnd,
+        //optional number of tokens each account can mint from the series
+        this.mint_limit_per_account = mintLimitPerAccount,
+        //while the presale is active only accounts on the series allowlist can mint
+        this.presale_active = presaleActive,
+        //optional metadata every token of the series shows until the series is revealed
+        this.placeholder_metadata = placeholderMetadata,
+        //false while the placeholder metadata is shown, revealing can't be undone
+        this.revealed = revealed,
+        //optional pool of metadata left to hand out, every mint receives a random entry from it
+        this.metadata_pool = metadataPool,
+        //set when the series was locked with frozen royalties, after which update_series_royalty is rejected
+        this.royalty_frozen = royaltyFrozen,
+        //hex encoded sha256 merkle root of the accounts allowed to mint with a proof, null if unused
+        this.allowlist_merkle_root = allowlistMerkleRoot,
+        //hard cap on how many tokens can be minted, takes precedence over metadata.copies when set
+        this.max_supply = maxSupply,
+        //whether minters can return a paid mint with refund_mint while the refund window is open
+        this.refundable = refundable,
+        //set once the series_closed event was emitted after the minting window ended
+        this.closed_emitted = closedEmitted,
+        //key the series' per-account mint counts, allowlist and discount codes are stored under, kept across update_series_id
+        this.account_key = accountKey
+    }
+}
+
+//The mint precheck is returned from the mint_precheck view so UIs know whether a mint would go through.
+export class MintPrecheck {
+    can_mint: boolean;
+    reason?: string;
+    required_deposit: string;
+
+    constructor({
+        canMint,
+        reason,
+        requiredDeposit
+    }: {
+        canMint: boolean,
+        reason?: string,
+        requiredDeposit: string
+    }) {
+        //whether the mint would succeed
+        this.can_mint = canMint,
+        //the first reason blocking the mint, if any
+        this.reason = reason,
+        //the deposit (in yoctoNEAR) to attach to a mint of the account to itself: the price plus an estimate of the storage
+        this.required_deposit = requiredDeposit
+    }
+}
+
+//The mint quote is returned from the effective_mint_price view so checkouts know the full cost of a mint up front.
+export class MintQuote {
+    base_price: string;
+    discount: string;
+    platform_fee: string;
+    storage_cost: string;
+    required_deposit: string;
+
+    constructor({
+        basePrice,
+        discount,
+        platformFee,
+        storageCost,
+        requiredDeposit
+    }: {
+        basePrice: string,
+        discount: string,
+        platformFee: string,
+        storageCost: string,
+        requiredDeposit: string
+    }) {
+        //price (in yoctoNEAR) of the series before any discount
+        this.base_price = basePrice,
+        //amount (in yoctoNEAR) the discount code takes off the price
+        this.discount = discount,
+        //part (in yoctoNEAR) of the discounted price that goes to the treasury, the series owner receives the rest
+        this.platform_fee = platformFee,
+        //estimate (in yoctoNEAR) of the storage deposit the mint needs, 0 if the account's storage balance covers it
+        this.storage_cost = storageCost,
+        //the deposit (in yoctoNEAR) to attach: the discounted price plus the storage estimate
+        this.required_deposit = requiredDeposit
+    }
+}
+
+//A discount code takes a percentage off the price of a series' mints until its uses run out. Codes are stored under
+//the sha256 hash of the code so the plaintext can't be read from the contract state.
+export class DiscountCode {
+    percent_off: number;
+    uses_left: number;
+
+    constructor({
+        percentOff,
+        usesLeft
+    }: {
+        percentOff: number,
+        usesLeft: number
+    }) {
+        //how much of the price (1 to 100 percent) the code takes off
+        this.percent_off = percentOff,
+        //how many more mints can redeem the code
+        this.uses_left = usesLeft
+    }
+}
+
+//A refundable mint holds the price of a paid mint of a refundable series until it's refunded or the refund window passes.
+export class RefundableMint {
+    series_id: number;
+    minter_id: string;
+    owner_id: string;
+    price: string;
+    minted_at: string;
+    refundable_until: string;
+
+    constructor({
+        seriesId,
+        minterId,
+        ownerId,
+        price,
+        mintedAt,
+        refundableUntil
+    }: {
+        seriesId: number,
+        minterId: string,
+        ownerId: string,
+        price: string,
+        mintedAt: string,
+        refundableUntil: string
+    }) {
+        //series the token was minted from
+        this.series_id = seriesId,
+        //account that paid for the mint and can refund it
+        this.minter_id = minterId,
+        //series owner at the time of the mint, who is paid once the mint can't be refunded anymore
+        this.owner_id = ownerId,
+        //price (in yoctoNEAR) that was paid, after any discount
+        this.price = price,
+        //block timestamp (in nanoseconds) of the mint, a token transferred since then can't be refunded
+        this.minted_at = mintedAt,
+        //block timestamp (in nanoseconds) the refund window closes at
+        this.refundable_until = refundableUntil
+    }
+}
+
+//An approval event is appended to a token's approval history whenever an account is approved or revoked.
+export class ApprovalEvent {
+    timestamp: string;
+    action: string;
+    account_id: string;
+    approval_id?: number;
+
+    constructor({
+        timestamp,
+        action,
+        accountId,
+        approvalId
+    }: {
+        timestamp: string,
+        action: string,
+        accountId: string,
+        approvalId?: number
+    }) {
+        //block timestamp (in nanoseconds) of the approval change
+        this.timestamp = timestamp,
+        //either "approve" or "revoke"
+        this.action = action,
+        //account that was approved or revoked
+        this.account_id = accountId,
+        //the approval ID that was given (approvals only)
+        this.approval_id = approvalId
+    }
+}
+
+//An auction for a token held in escrow by the built-in marketplace.
+export class Auction {
+    seller_id: string;
+    reserve: string;
+    end_at: string;
+    high_bid?: string;
+    high_bidder?: string;
+    min_bid_increment_bps: number;
+    extension_window: string;
+
+    constructor({
+        sellerId,
+        reserve,
+        endAt,
+        minBidIncrementBps,
+        extensionWindow
+    }: {
+        sellerId: string,
+        reserve: string,
+        endAt: string,
+        minBidIncrementBps: number,
+        extensionWindow: string
+    }) {
+        //account that put the token up for auction
+        this.seller_id = sellerId,
+        //minimum bid (in yoctoNEAR) that is accepted
+        this.reserve = reserve,
+        //block timestamp (in nanoseconds) after which no more bids are accepted
+        this.end_at = endAt,
+        //current highest bid (in yoctoNEAR) and who placed it
+        this.high_bid = null,
+        this.high_bidder = null,
+        //how much (in basis points) a new bid needs to beat the current high bid by
+        this.min_bid_increment_bps = minBidIncrementBps,
+        //bids placed this close (in nanoseconds) to the end push the end back by the same amount
+        this.extension_window = extensionWindow
+    }
+}
+
+//The auction state is what will be returned from the token_auction view.
+export class AuctionState {
+    seller_id: string;
+    reserve: string;
+    high_bid?: string;
+    high_bidder?: string;
+    end_at: string;
+    settleable: boolean;
+
+    constructor({
+        auction,
+        settleable
+    }: {
+        auction: Auction,
+        settleable: boolean
+    }) {
+        //account that put the token up for auction
+        this.seller_id = auction.seller_id,
+        //minimum bid (in yoctoNEAR) that is accepted
+        this.reserve = auction.reserve,
+        //current highest bid and bidder
+        this.high_bid = auction.high_bid,
+        this.high_bidder = auction.high_bidder,
+        //block timestamp (in nanoseconds) when bidding ends
+        this.end_at = auction.end_at,
+        //whether settle_auction can be called right now
+        this.settleable = settleable
+    }
+}
+
+//An offer is what will be returned from the token_offers view.
+export class Offer {
+    buyer_id: string;
+    amount: string;
+
+    constructor({
+        buyerId,
+        amount
+    }: {
+        buyerId: string,
+        amount: string
+    }) {
+        //account that made the offer
+        this.buyer_id = buyerId,
+        //amount (in yoctoNEAR) held in escrow for the offer
+        this.amount = amount
+    }
+}
+
+//The token state is returned from the token_state view so detail pages can load a token in one call.
+export class TokenState {
+    token: JsonToken;
+    transferable: boolean;
+    expired: boolean;
+    enforce_royalty_on_transfer: boolean;
+    auto_accept_price?: string;
+    auction?: AuctionState;
+    offers: Offer[];
+
+    constructor({
+        token,
+        transferable,
+        expired,
+        enforceRoyaltyOnTransfer,
+        autoAcceptPrice,
+        auction,
+        offers
+    }: {
+        token: JsonToken,
+        transferable: boolean,
+        expired: boolean,
+        enforceRoyaltyOnTransfer: boolean,
+        autoAcceptPrice?: string,
+        auction?: AuctionState,
+        offers: Offer[]
+    }) {
+        //same token as nft_token returns (ownership, series, metadata and royalty)
+        this.token = token,
+        //transfer restrictions coming from the token's series
+        this.transferable = transferable,
+        this.expired = expired,
+        this.enforce_royalty_on_transfer = enforceRoyaltyOnTransfer,
+        //price at or above which offers are accepted automatically
+        this.auto_accept_price = autoAcceptPrice,
+        //live auction (same as token_auction) and standing offers (same as token_offers)
+        this.auction = auction,
+        this.offers = offers
+    }
+}
+
+//The storage balance of an account registered through NEP-145 storage management.
+export class StorageBalance {
+    total: string;
+    available: string;
+
+    constructor({
+        total,
+        available
+    }: {
+        total: string,
+        available: string
+    }) {
+        //everything the account deposited (in yoctoNEAR)
+        this.total = total,
+        //what can be withdrawn, i.e. the total minus what's locked for registration and held tokens
+        this.available = available
+    }
+}
+
+//The bounds an account's storage balance has to stay within.
+export class StorageBalanceBounds {
+    min: string;
+    max?: string;
+
+    constructor({
+        min,
+        max
+    }: {
+        min: string,
+        max?: string
+    }) {
+        //minimum deposit needed to register and hold a token
+        this.min = min,
+        //no upper bound, accounts can pre-fund as many mints as they like
+        this.max = max
+    }
+}
+
+//The contract stats are returned from the nft_contract_stats view so dashboards can load the headline numbers at once.
+export class ContractStats {
+    series_count: number;
+    token_count: number;
+    owner_count: number;
+
+    constructor({
+        seriesCount,
+        tokenCount,
+        ownerCount
+    }: {
+        seriesCount: number,
+        tokenCount: number,
+        ownerCount: number
+    }) {
+        //number of series on the contract
+        this.series_count = seriesCount,
+        //number of tokens on the contract
+        this.token_count = tokenCount,
+        //number of distinct accounts that own at least one token
+        this.owner_count = ownerCount
+    }
+}
+
+//The mint window is returned from the series_mint_window view so UIs can show a countdown.
+export class MintWindow {
+    mint_start?: string;
+    mint_end?: string;
+    block_timestamp: string;
+
+    constructor({
+        mintStart,
+        mintEnd,
+        blockTimestamp
+    }: {
+        mintStart?: string,
+        mintEnd?: string,
+        blockTimestamp: string
+    }) {
+        //block timestamps (in nanoseconds) minting opens and closes at, null means no limit on that side
+        this.mint_start = mintStart,
+        this.mint_end = mintEnd,
+        //current block timestamp (in nanoseconds) the window can be compared against
+        this.block_timestamp = blockTimestamp
+    }
+}
+
+//The contract config is returned from the contract_config view so admin panels can load every setting at once.
+export class ContractConfig {
+    owner_id: string;
+    platform_royalty_account: string;
+    platform_royalty_bps: number;
+    market_fee_recipient: string;
+    market_fee_bps: number;
+    accumulate_royalties: boolean;
+    emit_events: boolean;
+    max_royalty_bps: number;
+    default_royalty: { [accountId: string]: number };
+    pending_owner: string;
+    paused: boolean;
+    treasury_id: string;
+    platform_fee_bps: number;
+    refund_window_ns: string;
+    roles_enumerable: boolean;
+
+    constructor({
+        ownerId,
+        platformRoyaltyAccount,
+        platformRoyaltyBps,
+        marketFeeRecipient,
+        marketFeeBps,
+        accumulateRoyalties,
+        emitEvents,
+        maxRoyaltyBps,
+        defaultRoyalty,
+        pendingOwner,
+        paused,
+        treasuryId,
+        platformFeeBps,
+        refundWindowNs,
+        rolesEnumerable
+    }: {
+        ownerId: string,
+        platformRoyaltyAccount: string,
+        platformRoyaltyBps: number,
+        marketFeeRecipient: string,
+        marketFeeBps: number,
+        accumulateRoyalties: boolean,
+        emitEvents: boolean,
+        maxRoyaltyBps: number,
+        defaultRoyalty: { [accountId: string]: number },
+        pendingOwner: string,
+        paused: boolean,
+        treasuryId: string,
+        platformFeeBps: number,
+        refundWindowNs: string,
+        rolesEnumerable: boolean
+    }) {
+        //owner of the contract
+        this.owner_id = ownerId,
+        //account that receives the platform royalty on new series
+        this.platform_royalty_account = platformRoyaltyAccount,
+        //platform royalty (in basis points) injected into new series
+        this.platform_royalty_bps = platformRoyaltyBps,
+        //account that receives the fee on built-in marketplace sales
+        this.market_fee_recipient = marketFeeRecipient,
+        //fee (in basis points) taken from built-in marketplace sales
+        this.market_fee_bps = marketFeeBps,
+        //whether built-in marketplace royalties are credited for claim_royalties instead of sent
+        this.accumulate_royalties = accumulateRoyalties,
+        //whether mint, transfer and burn events are logged
+        this.emit_events = emitEvents,
+        //cap (in basis points) on the total royalty paid out, series above it are scaled down at payout time
+        this.max_royalty_bps = maxRoyaltyBps,
+        //royalty copied into new series created without one
+        this.default_royalty = defaultRoyalty,
+        //the account that was proposed as the next owner, if any
+        this.pending_owner = pendingOwner,
+        //whether the contract is paused, which blocks every state-changing method
+        this.paused = paused,
+        //account that receives the platform fee of paid mints, if any
+        this.treasury_id = treasuryId,
+        //share (in basis points) of paid mints sent to the treasury
+        this.platform_fee_bps = platformFeeBps,
+        //how long (in nanoseconds) paid mints of refundable series can be refunded for
+        this.refund_window_ns = refundWindowNs,
+        //whether get_approved_minters and get_approved_creators list the full roster, false until an older
+        //deployment has finished migrate_approved_accounts
+        this.roles_enumerable = rolesEnumerable
+    }
+}
+
+//An admin action is appended to the on-chain admin log every time the owner mutates the contract.
+export class AdminAction {
+    timestamp: string;
+    actor: string;
+    description: string;
+
+    constructor({
+        timestamp,
+        actor,
+        description
+    }: {
+        timestamp: string,
+        actor: string,
+        description: string
+    }) {
+        //block timestamp (in nanoseconds) when the action happened
+        this.timestamp = timestamp,
+        //account that performed the action
+        this.actor = actor,
+        //human readable description of what was changed
+        this.description = description
     }
 }
 
@@ -230,4 +898,21 @@ export function internalNftMetadata({
     contract: Contract
 }): NFTContractMetadata {
     return contract.metadata;
+}
+
+//get the identifiers of every standard and extension this contract implements
+export function internalSupportedInterfaces({
+    contract
+}:{
+    contract: Contract
+}): string[] {
+    return [
+        "nep171", // core
+        "nep177", // metadata
+        "nep178", // approval management
+        "nep181", // enumeration
+        "nep199", // royalties and payouts
+        "nep145", // storage management
+        "nep297", // events
+    ];
 }
\ No newline at end of file
@@ -1,11 +1,17 @@
 import { assert, bytes, near } from "near-sdk-js";
 import { Contract, NFT_METADATA_SPEC, NFT_STANDARD_NAME } from ".";
-import { assertOneYocto, internalAddTokenToOwner, internalRemoveTokenFromOwner, internalTransfer, refundDeposit, refundApprovedAccountIds } from "./internal";
-import { JsonToken, Series, Token, TokenMetadata } from "./metadata";
+import { activeApprovalId, assertOneYocto, bytesForApprovedAccountId, isApprovalExpired, assertPlainTransferAllowed, fillMetadataTemplate, internalAddTokenToOwner, internalBurnToken, internalRecordApprovalEvent, internalRecordTokenEvent, isSeriesExpired, isSeriesTransferable, internalRemoveTokenFromOwner, lazyUnmintedToken, internalTransfer, refundDeposit, refundApprovedAccountIds, tokenEdition, eventsEnabled } from "./internal";
+import { internalRefundOffers, internalTokenAuction, internalTokenOffers } from "./market";
+import { JsonToken, Series, Token, TokenMetadata, TokenState } from "./metadata";
 
 const GAS_FOR_RESOLVE_TRANSFER = 40_000_000_000_000;
 const GAS_FOR_NFT_ON_TRANSFER = 35_000_000_000_000;
 
+//maximum number of tokens that can be moved in a single nft_distribute call
+const MAX_DISTRIBUTION_LEN = 50;
+//each transfer costs a few Tgas, so this keeps a full batch well inside the 300 Tgas limit
+const MAX_BATCH_TRANSFER_LEN = 50;
+
 //get the information for a specific token ID
 export function internalNftToken({
     contract,
@@ -14,7 +20,11 @@ export function internalNftToken({
     contract: Contract, 
     tokenId: string 
 }) {
+    //unminted editions of lazy series are derived instead of stored
     let token = contract.tokensById.get(tokenId) as Token;
+    if (token == null) {
+        token = lazyUnmintedToken(contract, tokenId);
+    }
     if (token == null) {
         near.panic("no token");
     }
@@ -23,14 +33,41 @@ export function internalNftToken({
     if (curSeries == null) {
         near.panic("no series");
     }
+    //unrevealed series show their placeholder for every token, overrides included. Series created before reveals
+    //existed are revealed. Otherwise tokens that were assigned their own metadata at mint use it, the rest fall back
+    //to the series metadata, and any per-token override is merged on top field by field
     let metadata = curSeries.metadata;
-    let editionNumber = tokenId.split(":")[1];
+    let override: TokenMetadata = null;
+    if (curSeries.revealed == false && curSeries.placeholder_metadata != null) {
+        metadata = curSeries.placeholder_metadata;
+    } else {
+        if (token.metadata != null) {
+            metadata = token.metadata;
+        }
+        override = contract.tokenMetadataOverrides.get(tokenId) as TokenMetadata;
+        if (override != null) {
+            metadata = Object.assign({}, metadata);
+            Object.keys(override).forEach(field => {
+                if (override[field] != null) {
+                    metadata[field] = override[field];
+                }
+            });
+        }
+    }
+    let editionNumber = tokenEdition(tokenId, token).toString();
+    let titleOverridden = override != null && override.title != null;
 
-    if (metadata.title != null) {
+    //templated titles render the placeholders in place, otherwise the edition number is appended
+    //(unless the title was overridden for this token, then it's shown as it is)
+    if (metadata.title != null && (metadata.title.includes("{edition}") || metadata.title.includes("{series_id}"))) {
+        metadata.title = fillMetadataTemplate(metadata.title, token.series_id, editionNumber);
+    } else if (metadata.title != null && !titleOverridden) {
         metadata.title = `${metadata.title} - ${editionNumber}`;
-    } else {
+    } else if (metadata.title == null) {
         metadata.title = `Series ${token.series_id} : Edition ${editionNumber}`;
     }
+    metadata.media = fillMetadataTemplate(metadata.media, token.series_id, editionNumber);
+    metadata.reference = fillMetadataTemplate(metadata.reference, token.series_id, editionNumber);
 
     let jsonToken = new JsonToken({
         seriesId: token.series_id,
@@ -44,6 +81,72 @@ export function internalNftToken({
     return jsonToken;
 }
 
+//get everything a detail page needs about a token in one call, or null if it doesn't exist
+export function internalTokenState({
+    contract,
+    tokenId
+}:{ 
+    contract: Contract, 
+    tokenId: string 
+}): TokenState {
+    let token = contract.tokensById.get(tokenId) as Token;
+    if (token == null) {
+        return null;
+    }
+    // @ts-ignore
+    let series = contract.seriesById.get(token.series_id) as Series;
+    //the nested nft_token can't be built without the series either, so fail the same way it does
+    if (series == null) {
+        near.panic("no series");
+    }
+
+    //the auto accept price only counts while the account that set it still owns the token
+    let autoAccept = contract.autoAcceptByTokenId.get(tokenId) as [string, string];
+    let autoAcceptPrice = autoAccept != null && autoAccept[0] == token.owner_id ? autoAccept[1] : null;
+
+    return new TokenState({
+        token: internalNftToken({ contract, tokenId }),
+        transferable: isSeriesTransferable(series),
+        expired: isSeriesExpired(series),
+        enforceRoyaltyOnTransfer: series.enforce_royalty_on_transfer == true,
+        autoAcceptPrice,
+        auction: internalTokenAuction({ contract, tokenId }),
+        offers: internalTokenOffers({ contract, tokenId })
+    });
+}
+
+//get the rolling [block timestamp, event type] summary of a token's mints, transfers, burns and approvals
+export function internalTokenEventSummary({
+    contract,
+    tokenId
+}:{ 
+    contract: Contract, 
+    tokenId: string 
+}): [string, string][] {
+    let events = contract.tokenEvents.get(tokenId) as [string, string][];
+    return events != null ? events : [];
+}
+
+//get the owner of a token, or null if it doesn't exist. Stored tokens are read straight from tokensById without
+//building the JsonToken or loading the series, so it's cheaper than nft_token for ownership checks. Unminted
+//editions of lazy series are owned by the series owner, the same as in nft_token
+export function internalTokenOwner({
+    contract,
+    tokenId
+}:{ 
+    contract: Contract, 
+    tokenId: string 
+}): string {
+    let token = contract.tokensById.get(tokenId) as Token;
+    if (token == null) {
+        token = lazyUnmintedToken(contract, tokenId);
+    }
+    if (token == null) {
+        return null;
+    }
+    return token.owner_id;
+}
+
 //implementation of the nft_transfer method. This transfers the NFT from the current owner to the receiver. 
 export function internalNftTransfer({
     contract,
@@ -51,17 +154,21 @@ export function internalNftTransfer({
     tokenId,
     approvalId,
     memo,
+    retainApprovalFor
 }:{
     contract: Contract, 
     receiverId: string, 
     tokenId: string, 
     approvalId: number
-    memo: string
+    memo: string,
+    retainApprovalFor?: string
 }) {
     //assert that the user attached exactly 1 yoctoNEAR. This is for security and so that the user will be redirected to the NEAR wallet. 
     assertOneYocto();
     //get the sender to transfer the token from the sender to the receiver
     let senderId = near.predecessorAccountId();
+    //series that enforce royalties only allow value transfers through nft_transfer_payout
+    assertPlainTransferAllowed(contract, tokenId, senderId, receiverId);
 
     //call the internal transfer method and get back the previous token so we can refund the approved account IDs
     let previousToken = internalTransfer(
@@ -73,10 +180,29 @@ export function internalNftTransfer({
         memo,
     );
 
+    //optionally keep one operator (e.g. a bridge) approved under the new owner with a fresh approval ID
+    let approvedAccountIds = previousToken.approved_account_ids;
+    if (retainApprovalFor != null && retainApprovalFor != receiverId && approvedAccountIds.hasOwnProperty(retainApprovalFor) && !isApprovalExpired(previousToken, retainApprovalFor)) {
+        let token = contract.tokensById.get(tokenId) as Token;
+        let newApprovalId = token.next_approval_id;
+        token.approved_account_ids[retainApprovalFor] = newApprovalId;
+        token.next_approval_id = newApprovalId + 1;
+        //the retained approval keeps its expiry
+        if (previousToken.approval_expires_at != null && previousToken.approval_expires_at[retainApprovalFor] != null) {
+            token.approval_expires_at = { [retainApprovalFor]: previousToken.approval_expires_at[retainApprovalFor] };
+        }
+        contract.tokensById.set(tokenId, token);
+        internalRecordApprovalEvent(contract, tokenId, "approve", retainApprovalFor, newApprovalId);
+
+        //the retained approval keeps its storage, so it isn't refunded
+        approvedAccountIds = Object.assign({}, approvedAccountIds);
+        delete approvedAccountIds[retainApprovalFor];
+    }
+
     //we refund the owner for releasing the storage used up by the approved account IDs
     refundApprovedAccountIds(
         previousToken.owner_id,
-        previousToken.approved_account_ids
+        approvedAccountIds
     );
 }
 
@@ -100,6 +226,8 @@ export function internalNftTransferCall({
     assertOneYocto();
     //get the sender to transfer the token from the sender to the receiver
     let senderId = near.predecessorAccountId();
+    //series that enforce royalties only allow value transfers through nft_transfer_payout
+    assertPlainTransferAllowed(contract, tokenId, senderId, receiverId);
 
     //call the internal transfer method and get back the previous token so we can refund the approved account IDs
     let previousToken = internalTransfer(
@@ -211,6 +339,7 @@ export function internalResolveTransfer({
 
     //we inset the token b  ack into the tokens_by_id collection
     contract.tokensById.set(tokenId, token);
+    internalRecordTokenEvent(contract, tokenId, "transfer");
 
     /*
         We need to log that the NFT was reverted back to the original owner.
@@ -243,8 +372,223 @@ export function internalResolveTransfer({
     }
 
     // Log the serialized json.
-    near.log(JSON.stringify(nftTransferLog));
+    if (eventsEnabled(contract)) {
+        near.log(JSON.stringify(nftTransferLog));
+    }
 
     //return false
     return false
+}
+
+//burn a token whose series has expired. Anyone can call this once the expiry has passed.
+//The owner is refunded for the storage used by the token's approvals; the storage of the token itself
+//was paid for by the contract at mint time, so the released balance stays on the contract.
+export function internalExpireToken({
+    contract,
+    tokenId
+}:{
+    contract: Contract,
+    tokenId: string
+}) {
+    let token = contract.tokensById.get(tokenId) as Token;
+    if (token == null) {
+        near.panic("no token");
+    }
+    // @ts-ignore
+    let series = contract.seriesById.get(token.series_id) as Series;
+    assert(series != null && isSeriesExpired(series), "Token has not expired");
+    //an escrowed token still backs the high bid, settle_auction refunds it and hands the token back first
+    assert(contract.auctionsByTokenId.get(tokenId) == null, "Token is being auctioned");
+
+    let burnedToken = internalBurnToken(contract, tokenId, "expired");
+    contract.autoAcceptByTokenId.remove(tokenId);
+    internalRefundOffers(contract, tokenId);
+
+    //we refund the owner for releasing the storage used up by the approved account IDs
+    refundApprovedAccountIds(burnedToken.owner_id, burnedToken.approved_account_ids);
+}
+
+//permanently burn a token. Callable by the token owner or an account approved on the token.
+//Any approvals are cleared as part of the burn and their storage is refunded to the owner.
+//The rest of the storage freed by the burn is refunded to the caller.
+export function internalNftBurn({
+    contract,
+    tokenId,
+    memo
+}:{
+    contract: Contract,
+    tokenId: string,
+    memo?: string
+}) {
+    //assert that the user attached exactly 1 yoctoNEAR for security reasons
+    assertOneYocto();
+    let callerId = near.predecessorAccountId();
+
+    let token = contract.tokensById.get(tokenId) as Token;
+    if (token == null) {
+        near.panic("no token");
+    }
+    assert(callerId == token.owner_id || activeApprovalId(token, callerId) != null, "Unauthorized");
+    internalBurnAndRefundStorage(contract, tokenId, callerId, memo);
+}
+
+//burn a token, refund its offers and approvals and pay the storage it released out to the caller. The caller needs
+//to have checked that the token can be burned
+export function internalBurnAndRefundStorage(contract: Contract, tokenId: string, callerId: string, memo?: string) {
+    //standing offers give their buyers back the offer and its storage, so they're refunded before the
+    //storage is measured and don't get paid out to the caller a second time
+    internalRefundOffers(contract, tokenId);
+
+    //measure the storage before the burn so we can figure out how much was released
+    let initialStorageUsage = near.storageUsage();
+
+    let burnedToken = internalBurnToken(contract, tokenId, memo);
+    contract.autoAcceptByTokenId.remove(tokenId);
+
+    //the approvals were paid for by the owner so they get that part back
+    let approvedAccountIds = Object.keys(burnedToken.approved_account_ids);
+    let approvalBytes = approvedAccountIds.map(e => bytesForApprovedAccountId(e)).reduce((partialSum, a) => partialSum + a, 0);
+    refundApprovedAccountIds(burnedToken.owner_id, burnedToken.approved_account_ids);
+
+    //refund the remaining released storage to the caller
+    let releasedBytes = initialStorageUsage.valueOf() - near.storageUsage().valueOf() - BigInt(approvalBytes);
+    if (releasedBytes > BigInt(0)) {
+        const promise = near.promiseBatchCreate(callerId);
+        near.promiseBatchActionTransfer(promise, releasedBytes * near.storageByteCost().valueOf());
+    }
+}
+
+//transfer several tokens in one call. Each entry is [receiver_id, token_id, approval_id, memo] and goes
+//through the same owner/approval checks as nft_transfer. If any single transfer is invalid, the whole call reverts.
+export function internalNftBatchTransfer({
+    contract,
+    transfers
+}:{
+    contract: Contract,
+    transfers: [string, string, number, string][]
+}) {
+    //assert that the user attached exactly 1 yoctoNEAR for security reasons
+    assertOneYocto();
+    let senderId = near.predecessorAccountId();
+    assert(transfers.length <= MAX_BATCH_TRANSFER_LEN, `Cannot transfer more than ${MAX_BATCH_TRANSFER_LEN} tokens at once (gas limit), split the batch`);
+
+    //group the transferred token IDs by old owner, receiver and memo for the event log
+    let groups: string[] = [];
+    let tokenIdsByGroup: { [group: string]: string[] } = {};
+    for (let i = 0; i < transfers.length; i++) {
+        let [receiverId, tokenId, approvalId, memo] = transfers[i];
+        //series that enforce royalties only allow value transfers through nft_transfer_payout
+        assertPlainTransferAllowed(contract, tokenId, senderId, receiverId);
+
+        let previousToken = internalTransfer(contract, senderId, receiverId, tokenId, approvalId, memo, false);
+        //we refund the owner for releasing the storage used up by the approved account IDs
+        refundApprovedAccountIds(previousToken.owner_id, previousToken.approved_account_ids);
+
+        let group = JSON.stringify([previousToken.owner_id, receiverId, memo != null ? memo : null]);
+        if (tokenIdsByGroup[group] == null) {
+            groups.push(group);
+            tokenIdsByGroup[group] = [];
+        }
+        tokenIdsByGroup[group].push(tokenId);
+    }
+
+    // Construct the transfer log as per the events standard.
+    let nftTransferLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The data related with the event stored in a vector.
+        event: "nft_transfer",
+        // One entry per old owner and receiver.
+        data: groups.map(group => {
+            let [oldOwnerId, receiverId, memo] = JSON.parse(group);
+            return {
+                // The optional authorized account ID to transfer the token on behalf of the old owner.
+                authorized_id: senderId != oldOwnerId ? senderId : undefined,
+                // The old owner's account ID.
+                old_owner_id: oldOwnerId,
+                // The account ID of the new owner of the tokens.
+                new_owner_id: receiverId,
+                // A vector containing the token IDs as strings.
+                token_ids: tokenIdsByGroup[group],
+                // An optional memo to include.
+                memo: memo != null ? memo : undefined,
+            }
+        })
+    }
+
+    // Log the serialized json.
+    if (eventsEnabled(contract)) {
+        near.log(JSON.stringify(nftTransferLog));
+    }
+}
+
+//distribute already minted tokens owned by the caller to a list of recipients (approved minters only).
+//If any single token can't be transferred, the whole call reverts.
+export function internalNftDistribute({
+    contract,
+    distributions
+}:{
+    contract: Contract,
+    distributions: [string, string][]
+}) {
+    //assert that the user attached exactly 1 yoctoNEAR for security reasons
+    assertOneYocto();
+    let senderId = near.predecessorAccountId();
+    if(senderId != near.currentAccountId()) {
+        assert(contract.approvedMinters.contains(senderId), "Not approved minter");
+    }
+    assert(distributions.length <= MAX_DISTRIBUTION_LEN, `Cannot distribute more than ${MAX_DISTRIBUTION_LEN} tokens at once`);
+
+    //group the transferred token IDs by their receiver for the event log
+    let receivers: string[] = [];
+    let tokenIdsByReceiver: { [accountId: string]: string[] } = {};
+    for (let i = 0; i < distributions.length; i++) {
+        let [tokenId, receiverId] = distributions[i];
+        let token = contract.tokensById.get(tokenId) as Token;
+        if (token == null) {
+            near.panic("no token");
+        }
+        //distributions only move tokens the caller owns
+        assert(token.owner_id == senderId, `Token ${tokenId} is not owned by the caller`);
+        //distributions are plain transfers, so series that enforce royalties need them to go through nft_transfer_payout
+        assertPlainTransferAllowed(contract, tokenId, senderId, receiverId);
+
+        let previousToken = internalTransfer(contract, senderId, receiverId, tokenId, null, null, false);
+        //we refund the owner for releasing the storage used up by the approved account IDs
+        refundApprovedAccountIds(previousToken.owner_id, previousToken.approved_account_ids);
+
+        if (tokenIdsByReceiver[receiverId] == null) {
+            receivers.push(receiverId);
+            tokenIdsByReceiver[receiverId] = [];
+        }
+        tokenIdsByReceiver[receiverId].push(tokenId);
+    }
+
+    // Construct the transfer log as per the events standard.
+    let nftTransferLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The data related with the event stored in a vector.
+        event: "nft_transfer",
+        // One entry per receiver.
+        data: receivers.map(receiverId => {
+            return {
+                // The old owner's account ID.
+                old_owner_id: senderId,
+                // The account ID of the new owner of the tokens.
+                new_owner_id: receiverId,
+                // A vector containing the token IDs as strings.
+                token_ids: tokenIdsByReceiver[receiverId],
+            }
+        })
+    }
+
+    // Log the serialized json.
+    if (eventsEnabled(contract)) {
+        near.log(JSON.stringify(nftTransferLog));
+    }
 }
\ No newline at end of file
@@ -1,5 +1,8 @@
-import { assert, near } from "near-sdk-js";
-import { Contract } from ".";
+import { assert, near, UnorderedSet } from "near-sdk-js";
+import { Contract, CONTRACT_STATE_VERSION, NFT_METADATA_SPEC, NFT_STANDARD_NAME } from ".";
+import { eventsEnabled, internalRecordAdminAction, isContractOwner, isValidAccountId } from "./internal";
+import { AdminAction, ContractConfig, NFTContractMetadata } from "./metadata";
+import { assertValidRoyalty, hasDefaultRoyalty, maxRoyaltyBps } from "./royalty";
 
 export function internalAddApprovedMinters({
     contract,
@@ -8,11 +11,14 @@ export function internalAddApprovedMinters({
     contract: Contract, 
     accountId: string
 }): void {  
-    // Assert the predecessor is the current account ID
+    // Assert the predecessor is the contract owner
     const predecessorAccountId = near.predecessorAccountId();
-    assert(predecessorAccountId === near.currentAccountId(), "Only the current account can add approved minters");
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can add approved minters");
     
     contract.approvedMinters.set(accountId);
+    //keep the enumerable copy in sync for get_approved_minters
+    contract.approvedMintersList.set(accountId);
+    internalRecordAdminAction(contract, `Added approved minter ${accountId}`);
 }
 
 export function internalRemoveApprovedMinters({
@@ -22,11 +28,13 @@ export function internalRemoveApprovedMinters({
     contract: Contract, 
     accountId: string
 }): void {  
-    // Assert the predecessor is the current account ID
+    // Assert the predecessor is the contract owner
     const predecessorAccountId = near.predecessorAccountId();
-    assert(predecessorAccountId === near.currentAccountId(), "Only the current account can remove approved minters");
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can remove approved minters");
     
     contract.approvedMinters.remove(accountId);
+    contract.approvedMintersList.remove(accountId);
+    internalRecordAdminAction(contract, `Removed approved minter ${accountId}`);
 }
 
 export function internalIsApprovedMinter({
@@ -46,11 +54,14 @@ export function internalAddApprovedCreator({
     contract: Contract, 
     accountId: string
 }): void {  
-    // Assert the predecessor is the current account ID
+    // Assert the predecessor is the contract owner
     const predecessorAccountId = near.predecessorAccountId();
-    assert(predecessorAccountId === near.currentAccountId(), "Only the current account can add approved creators");
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can add approved creators");
     
     contract.approvedCreators.set(accountId);
+    //keep the enumerable copy in sync for get_approved_creators
+    contract.approvedCreatorsList.set(accountId);
+    internalRecordAdminAction(contract, `Added approved creator ${accountId}`);
 }
 
 export function internalRemoveApprovedCreator({
@@ -60,11 +71,13 @@ export function internalRemoveApprovedCreator({
     contract: Contract, 
     accountId: string
 }): void {  
-    // Assert the predecessor is the current account ID
+    // Assert the predecessor is the contract owner
     const predecessorAccountId = near.predecessorAccountId();
-    assert(predecessorAccountId === near.currentAccountId(), "Only the current account can remove approved creators");
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can remove approved creators");
     
     contract.approvedCreators.remove(accountId);
+    contract.approvedCreatorsList.remove(accountId);
+    internalRecordAdminAction(contract, `Removed approved creator ${accountId}`);
 }
 
 export function internalIsApprovedCreator({
@@ -75,4 +88,620 @@ export function internalIsApprovedCreator({
     accountId: string
 }): boolean {  
     return contract.approvedCreators.contains(accountId);
+}
+
+//paginate through one of the approved account sets
+function paginateAccountSet(accounts: UnorderedSet, fromIndex?: string, limit?: number): string[] {
+    //where to start pagination - if we have a fromIndex, we'll use that - otherwise start from 0 index
+    let start = fromIndex ? parseInt(fromIndex) : 0;
+    //take the first "limit" elements in the array. If we didn't specify a limit, use 50
+    let max = limit ? limit : 50;
+
+    return (accounts.toArray() as string[]).slice(start, start + max);
+}
+
+//paginate through the approved minters
+export function internalGetApprovedMinters({
+    contract,
+    fromIndex,
+    limit
+}:{ 
+    contract: Contract, 
+    fromIndex?: string, 
+    limit?: number
+}): string[] {
+    return paginateAccountSet(contract.approvedMintersList, fromIndex, limit);
+}
+
+//paginate through the approved creators
+export function internalGetApprovedCreators({
+    contract,
+    fromIndex,
+    limit
+}:{ 
+    contract: Contract, 
+    fromIndex?: string, 
+    limit?: number
+}): string[] {
+    return paginateAccountSet(contract.approvedCreatorsList, fromIndex, limit);
+}
+
+/*
+    copy approved minters and creators that were added before the enumerable lists existed into them.
+    LookupSets can't be listed, so the owner passes in the accounts to carry over. Only accounts that are
+    actually approved are copied, the LookupSets stay the source of truth for the role checks, and the
+    migration can be run in several calls for long rosters. The owner passes complete with the last page.
+*/
+export function internalMigrateApprovedAccounts({
+    contract,
+    minters,
+    creators,
+    complete
+}:{ 
+    contract: Contract, 
+    minters: string[],
+    creators: string[],
+    //whether this is the last page of the roster
+    complete?: boolean
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can migrate approved accounts");
+
+    let moved = 0;
+    (minters != null ? minters : []).forEach(accountId => {
+        if (contract.approvedMinters.contains(accountId) && contract.approvedMintersList.set(accountId)) {
+            moved++;
+        }
+    });
+    (creators != null ? creators : []).forEach(accountId => {
+        if (contract.approvedCreators.contains(accountId) && contract.approvedCreatorsList.set(accountId)) {
+            moved++;
+        }
+    });
+    //the lists are only complete once the owner has carried the whole roster over, which only they can tell
+    if (complete == true) {
+        contract.rolesEnumerable = true;
+    }
+    internalRecordAdminAction(contract, `Migrated ${moved} approved accounts${complete == true ? ", roster complete" : ""}`);
+}
+
+export function internalSetPlatformRoyalty({
+    contract,
+    accountId,
+    bps
+}:{ 
+    contract: Contract, 
+    accountId: string,
+    bps: number
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can set the platform royalty");
+    assert(bps >= 0 && bps <= 10000, "Platform royalty must be between 0 and 10000 basis points");
+    
+    contract.platformRoyaltyAccount = accountId;
+    contract.platformRoyaltyBps = bps;
+    internalRecordAdminAction(contract, `Set platform royalty to ${bps} bps for ${accountId}`);
+}
+
+export function internalSetMediaPolicy({
+    contract,
+    enabled,
+    allowDataUris
+}:{ 
+    contract: Contract, 
+    enabled: boolean,
+    allowDataUris: boolean
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can set the media policy");
+    
+    contract.mediaPolicyEnabled = enabled;
+    contract.allowDataUris = allowDataUris;
+    internalRecordAdminAction(contract, `Set media policy enabled=${enabled} allow_data_uris=${allowDataUris}`);
+}
+
+//choose whether royalties from built-in marketplace sales are credited for claim_royalties instead of sent right away
+export function internalSetAccumulateRoyalties({
+    contract,
+    enabled
+}:{ 
+    contract: Contract, 
+    enabled: boolean
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can set the royalty payout mode");
+    
+    contract.accumulateRoyalties = enabled;
+    internalRecordAdminAction(contract, `Set accumulate royalties to ${enabled}`);
+}
+
+//turn mint, transfer and burn events on or off. Turning them off is meant for bulk operations that would exceed
+//the log limits, indexers have to resync anything that happens in the meantime from an off-chain snapshot
+export function internalSetEmitEvents({
+    contract,
+    enabled
+}:{ 
+    contract: Contract, 
+    enabled: boolean
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can toggle events");
+    
+    contract.emitEvents = enabled;
+    internalRecordAdminAction(contract, `Set emit events to ${enabled}`);
+}
+
+//pause or unpause the contract. While paused every state-changing method panics, views and owner settings keep working
+export function internalSetPaused({
+    contract,
+    paused
+}:{ 
+    contract: Contract, 
+    paused: boolean
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can pause the contract");
+    
+    contract.paused = paused;
+    internalRecordAdminAction(contract, `Set paused to ${paused}`);
+
+    // Construct the pause log as per the events standard.
+    let pauseLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The data related with the event stored in a vector.
+        event: paused ? "contract_pause" : "contract_unpause",
+        data: [
+            {
+                // The account that paused or unpaused the contract.
+                account_id: predecessorAccountId,
+            }
+        ]
+    }
+
+    near.log(`EVENT_JSON:${JSON.stringify(pauseLog)}`);
+}
+
+//cap the total royalty paid out for any series. Series whose stored royalty is above the cap are scaled down at
+//payout time and the seller keeps the difference
+export function internalSetMaxRoyaltyBps({
+    contract,
+    bps
+}:{ 
+    contract: Contract, 
+    bps: number
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can set the royalty cap");
+    assert(bps >= 0 && bps <= 10000, "Royalty cap must be between 0 and 10000 basis points");
+    
+    contract.maxRoyaltyBps = bps;
+    internalRecordAdminAction(contract, `Set max royalty to ${bps} bps`);
+}
+
+//set the royalty that new series created without one start with. Passing null (or an empty map) removes the default.
+//Existing series keep the royalty they were created with
+export function internalSetDefaultRoyalty({
+    contract,
+    royalty
+}:{ 
+    contract: Contract, 
+    royalty?: { [accountId: string]: number }
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can set the default royalty");
+    if (royalty != null) {
+        assertValidRoyalty(royalty);
+    }
+    
+    //the contract state can't hold null fields, so an empty map means there's no default
+    contract.defaultRoyalty = royalty != null ? royalty : {};
+    internalRecordAdminAction(contract, `Set default royalty to ${JSON.stringify(contract.defaultRoyalty)}`);
+}
+
+export function internalAddAllowedMediaPrefix({
+    contract,
+    prefix
+}:{ 
+    contract: Contract, 
+    prefix: string
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can add allowed media prefixes");
+    
+    if (!contract.allowedMediaPrefixes.includes(prefix)) {
+        contract.allowedMediaPrefixes.push(prefix);
+    }
+    internalRecordAdminAction(contract, `Added allowed media prefix ${prefix}`);
+}
+
+export function internalRemoveAllowedMediaPrefix({
+    contract,
+    prefix
+}:{ 
+    contract: Contract, 
+    prefix: string
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can remove allowed media prefixes");
+    
+    contract.allowedMediaPrefixes = contract.allowedMediaPrefixes.filter(p => p != prefix);
+    internalRecordAdminAction(contract, `Removed allowed media prefix ${prefix}`);
+}
+
+export function internalGetAllowedMediaPrefixes({
+    contract
+}:{ 
+    contract: Contract
+}): string[] {  
+    return contract.allowedMediaPrefixes;
+}
+
+export function internalAddRoyaltyExemptAccount({
+    contract,
+    accountId
+}:{ 
+    contract: Contract, 
+    accountId: string
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can add royalty exempt accounts");
+    
+    contract.royaltyExemptAccounts.set(accountId);
+    internalRecordAdminAction(contract, `Added royalty exempt account ${accountId}`);
+}
+
+export function internalRemoveRoyaltyExemptAccount({
+    contract,
+    accountId
+}:{ 
+    contract: Contract, 
+    accountId: string
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can remove royalty exempt accounts");
+    
+    contract.royaltyExemptAccounts.remove(accountId);
+    internalRecordAdminAction(contract, `Removed royalty exempt account ${accountId}`);
+}
+
+export function internalIsRoyaltyExemptAccount({
+    contract,
+    accountId
+}:{ 
+    contract: Contract, 
+    accountId: string
+}): boolean {  
+    return contract.royaltyExemptAccounts.contains(accountId);
+}
+
+export function internalSetMarketFee({
+    contract,
+    accountId,
+    bps
+}:{ 
+    contract: Contract, 
+    accountId: string,
+    bps: number
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can set the market fee");
+    assert(bps >= 0 && bps <= 10000, "Market fee must be between 0 and 10000 basis points");
+    
+    contract.marketFeeRecipient = accountId;
+    contract.marketFeeBps = bps;
+    internalRecordAdminAction(contract, `Set market fee to ${bps} bps for ${accountId}`);
+}
+
+//log an ownership event as per the events standard
+function logOwnershipEvent(event: string, oldOwnerId: string, newOwnerId: string) {
+    let ownershipLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The data related with the event stored in a vector.
+        event,
+        data: [
+            {
+                // The current owner of the contract.
+                old_owner_id: oldOwnerId,
+                // The proposed (or new) owner of the contract.
+                new_owner_id: newOwnerId,
+            }
+        ]
+    }
+
+    near.log(`EVENT_JSON:${JSON.stringify(ownershipLog)}`);
+}
+
+//propose a new contract owner. The ownership only changes once the proposed account accepts it
+export function internalProposeOwner({
+    contract,
+    newOwnerId
+}:{ 
+    contract: Contract, 
+    newOwnerId: string
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can propose a new owner");
+    assert(isValidAccountId(newOwnerId), "Invalid account ID");
+    assert(newOwnerId != contract.owner_id, "Account is already the owner");
+
+    contract.pendingOwner = newOwnerId;
+    internalRecordAdminAction(contract, `Proposed ${newOwnerId} as the new owner`);
+    logOwnershipEvent("ownership_propose", contract.owner_id, newOwnerId);
+}
+
+//accept a pending ownership proposal. Only the proposed account can call this
+export function internalAcceptOwnership({
+    contract
+}:{ 
+    contract: Contract
+}): void {  
+    const predecessorAccountId = near.predecessorAccountId();
+    //an empty pending owner means nobody has been proposed
+    assert(contract.pendingOwner != null && contract.pendingOwner != "", "No pending owner");
+    assert(predecessorAccountId === contract.pendingOwner, "Only the pending owner can accept the ownership");
+
+    let oldOwnerId = contract.owner_id;
+    contract.owner_id = predecessorAccountId;
+    contract.pendingOwner = "";
+    internalRecordAdminAction(contract, `Ownership transferred from ${oldOwnerId} to ${predecessorAccountId}`);
+    logOwnershipEvent("ownership_accept", oldOwnerId, predecessorAccountId);
+}
+
+//cancel a pending ownership proposal
+export function internalCancelOwnershipProposal({
+    contract
+}:{ 
+    contract: Contract
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can cancel the ownership proposal");
+    assert(contract.pendingOwner != null && contract.pendingOwner != "", "No pending owner");
+
+    let pendingOwner = contract.pendingOwner;
+    contract.pendingOwner = "";
+    internalRecordAdminAction(contract, `Cancelled the ownership proposal for ${pendingOwner}`);
+    logOwnershipEvent("ownership_cancel", contract.owner_id, pendingOwner);
+}
+
+//make sure contract metadata keeps the supported spec and a name
+function assertValidContractMetadata(metadata: NFTContractMetadata) {
+    assert(metadata.spec == NFT_METADATA_SPEC, `Spec must be ${NFT_METADATA_SPEC}`);
+    assert(metadata.name != null && metadata.name.length > 0, "Name cannot be empty");
+}
+
+//replace the whole contract metadata
+export function internalUpdateContractMetadata({
+    contract,
+    metadata
+}:{ 
+    contract: Contract, 
+    metadata: NFTContractMetadata
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can update the contract metadata");
+    assertValidContractMetadata(metadata);
+
+    contract.metadata = metadata;
+    internalRecordAdminAction(contract, `Updated contract metadata`);
+}
+
+//set the name in the contract metadata
+export function internalSetContractName({
+    contract,
+    name
+}:{ 
+    contract: Contract, 
+    name: string
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can set the contract name");
+
+    let metadata = contract.metadata;
+    metadata.name = name;
+    assertValidContractMetadata(metadata);
+    contract.metadata = metadata;
+    internalRecordAdminAction(contract, `Set contract name to ${name}`);
+}
+
+//set (or clear, by passing null) the icon in the contract metadata
+export function internalSetContractIcon({
+    contract,
+    icon
+}:{ 
+    contract: Contract, 
+    icon?: string
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can set the contract icon");
+
+    let metadata = contract.metadata;
+    metadata.icon = icon;
+    contract.metadata = metadata;
+    internalRecordAdminAction(contract, `Set contract icon`);
+}
+
+//set (or clear, by passing null) the base URI in the contract metadata
+export function internalSetBaseUri({
+    contract,
+    baseUri
+}:{ 
+    contract: Contract, 
+    baseUri?: string
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can set the base URI");
+
+    let metadata = contract.metadata;
+    metadata.base_uri = baseUri;
+    contract.metadata = metadata;
+    internalRecordAdminAction(contract, `Set base URI to ${baseUri}`);
+}
+
+/*
+    bring the state of an upgraded deployment up to the current layout. Every call and view deserializes the
+    state first, which throws on collections the old state doesn't have yet, so this works on the raw STATE
+    instead. Fields the old state doesn't have yet are filled in with the defaults of a fresh contract, existing
+    fields are never touched (apart from null values that can't be deserialized), so running it again is safe.
+    Call it (as the contract account) right after deploying new code
+*/
+export function internalMigrate(): number {
+    assert(near.predecessorAccountId() === near.currentAccountId(), "Only the contract itself can call this method");
+
+    let rawState = near.storageRead("STATE");
+    assert(rawState != null, "Contract state is empty");
+    let state = JSON.parse(rawState);
+
+    //deployments from before the approved accounts became enumerable have empty lists until
+    //migrate_approved_accounts copies the roster over
+    if (state.rolesEnumerable === undefined) {
+        state.rolesEnumerable = false;
+    }
+    //the supply counter has to start from the tokens that already exist. The raw UnorderedMap only keeps
+    //a count in its keys vector
+    if (state.totalSupply === undefined) {
+        state.totalSupply = state.tokensById.keys.length;
+    }
+    //older layouts stored these as null, which deserialize can't handle
+    if (state.defaultRoyalty === null) {
+        state.defaultRoyalty = {};
+    }
+    if (state.pendingOwner === null) {
+        state.pendingOwner = "";
+    }
+    if (state.treasuryId === null) {
+        state.treasuryId = "";
+    }
+
+    //the serialized form of a fresh contract, so collections are filled in the way they're stored
+    let defaults = JSON.parse(JSON.stringify(new Contract({ owner_id: state.owner_id, metadata: state.metadata })));
+    let filled: string[] = [];
+    Object.keys(defaults).forEach(field => {
+        if (state[field] === undefined) {
+            state[field] = defaults[field];
+            filled.push(field);
+        }
+    });
+
+    let previousVersion = state.stateVersion != null ? state.stateVersion : 0;
+    state.stateVersion = CONTRACT_STATE_VERSION;
+    near.storageWrite("STATE", JSON.stringify(state));
+
+    //the state can be deserialized now, so the migration is recorded like any other owner action
+    let contract = new Contract({ owner_id: state.owner_id });
+    contract.deserialize();
+    internalRecordAdminAction(contract, `Migrated state from version ${previousVersion} to ${CONTRACT_STATE_VERSION}, filled in: ${filled.length > 0 ? filled.join(", ") : "nothing"}`);
+    contract.serialize();
+    return CONTRACT_STATE_VERSION;
+}
+
+//set the treasury and the share of paid mints it receives. Passing a null treasury turns the fee off
+export function internalSetPlatformFee({
+    contract,
+    treasuryId,
+    platformFeeBps
+}:{ 
+    contract: Contract, 
+    treasuryId?: string,
+    platformFeeBps: number
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can set the platform fee");
+    assert(platformFeeBps >= 0 && platformFeeBps <= 10000, "Platform fee must be between 0 and 10000 basis points");
+    assert(treasuryId == null || isValidAccountId(treasuryId), "Invalid treasury account ID");
+    
+    //an empty treasury means the fee is off
+    contract.treasuryId = treasuryId != null ? treasuryId : "";
+    contract.platformFeeBps = platformFeeBps;
+    internalRecordAdminAction(contract, `Set platform fee to ${platformFeeBps} bps for ${treasuryId}`);
+}
+
+//set how long paid mints of refundable series can be refunded for. Mints that already happened keep their window
+export function internalSetRefundWindow({
+    contract,
+    windowNs
+}:{ 
+    contract: Contract, 
+    windowNs: string
+}): void {  
+    // Assert the predecessor is the contract owner
+    const predecessorAccountId = near.predecessorAccountId();
+    assert(isContractOwner(contract, predecessorAccountId), "Only the owner can set the refund window");
+    assert(BigInt(windowNs) >= BigInt(0), "Refund window can't be negative");
+
+    contract.refundWindowNs = windowNs;
+    internalRecordAdminAction(contract, `Set refund window to ${windowNs} ns`);
+}
+
+//return every governance setting on the contract in a single object
+export function internalContractConfig({
+    contract
+}:{ 
+    contract: Contract
+}): ContractConfig {  
+    return new ContractConfig({
+        ownerId: contract.owner_id,
+        platformRoyaltyAccount: contract.platformRoyaltyAccount,
+        platformRoyaltyBps: contract.platformRoyaltyBps,
+        marketFeeRecipient: contract.marketFeeRecipient,
+        marketFeeBps: contract.marketFeeBps,
+        accumulateRoyalties: contract.accumulateRoyalties,
+        emitEvents: eventsEnabled(contract),
+        maxRoyaltyBps: maxRoyaltyBps(contract),
+        defaultRoyalty: hasDefaultRoyalty(contract) ? contract.defaultRoyalty : null,
+        pendingOwner: contract.pendingOwner != null && contract.pendingOwner != "" ? contract.pendingOwner : null,
+        paused: contract.paused == true,
+        treasuryId: contract.treasuryId != null && contract.treasuryId != "" ? contract.treasuryId : null,
+        platformFeeBps: contract.platformFeeBps != null ? contract.platformFeeBps : 0,
+        refundWindowNs: contract.refundWindowNs,
+        rolesEnumerable: contract.rolesEnumerable != false
+    });
+}
+
+//paginate through the admin log
+export function internalGetAdminLog({
+    contract,
+    fromIndex,
+    limit
+}:{ 
+    contract: Contract, 
+    fromIndex?: string, 
+    limit?: number
+}): AdminAction[] {
+    let actions: AdminAction[] = [];
+
+    //where to start pagination - if we have a fromIndex, we'll use that - otherwise start from 0 index
+    let start = fromIndex ? parseInt(fromIndex) : 0;
+    //take the first "limit" elements in the array. If we didn't specify a limit, use 50
+    let max = limit ? limit : 50;
+
+    for (let i = start; i < contract.adminLog.len() && i < start + max; i++) {
+        actions.push(contract.adminLog.get(i) as AdminAction);
+    }
+    return actions;
 }
\ No newline at end of file
@@ -1,43 +1,94 @@
 import { assert, near } from "near-sdk-js";
 import { Contract, NFT_METADATA_SPEC, NFT_STANDARD_NAME } from ".";
-import { assertAtLeastOneYocto, assertOneYocto, bytesForApprovedAccountId, internalAddTokenToOwner, internalTransfer, refundDeposit, refundApprovedAccountIds, refundApprovedAccountIdsIter, royaltyToPayout } from "./internal";
+import { assertAtLeastOneYocto, assertOneYocto, bytesForApprovedAccountId, internalAddTokenToOwner, internalTransfer, isValidAccountId, refundDeposit, refundApprovedAccountIds, refundApprovedAccountIdsIter, royaltyToPayout, assertNotPaused } from "./internal";
 import { Series, Token } from "./metadata";
 
-//calculates the payout for a token given the passed in balance. This is a view method
-export function internalNftPayout({
+//add an amount to what the contract owes a royalty recipient
+export function internalCreditRoyalty(contract: Contract, accountId: string, amount: bigint) {
+    if (amount <= BigInt(0)) {
+        return;
+    }
+    let owed = contract.royaltiesOwed.get(accountId) as string;
+    let total = (owed != null ? BigInt(owed) : BigInt(0)) + amount;
+    contract.royaltiesOwed.set(accountId, total.toString());
+}
+
+//get the royalties the contract has accumulated for an account and not yet paid out ("0" if none)
+export function internalRoyaltiesOwed({
     contract,
-    tokenId,
-    balance,
-    maxLenPayout
+    accountId
 }:{
-    contract: Contract, 
-    tokenId: string,
-    balance: bigint, 
-    maxLenPayout: number,
-}): { payout: {[key: string]: string }} {
-    //get the token object
-    let token = contract.tokensById.get(tokenId) as Token;
-    if (token == null) {
-        near.panic("no token");
+    contract: Contract,
+    accountId: string
+}): string {
+    let owed = contract.royaltiesOwed.get(accountId) as string;
+    return owed != null ? owed : "0";
+}
+
+//withdraw every royalty the contract has accumulated for the caller in a single transfer
+export function internalClaimRoyalties({
+    contract
+}:{
+    contract: Contract
+}): string {
+    assertNotPaused(contract);
+    let accountId = near.predecessorAccountId();
+    let owed = contract.royaltiesOwed.get(accountId) as string;
+    assert(owed != null && BigInt(owed) > BigInt(0), "No royalties to claim");
+
+    //zero the balance before sending so the claim can't be repeated
+    contract.royaltiesOwed.remove(accountId);
+    const promise = near.promiseBatchCreate(accountId);
+    near.promiseBatchActionTransfer(promise, BigInt(owed));
+    return owed;
+}
+
+//make sure a royalty map is valid: every key is a proper account ID, every share is a whole number of basis
+//points and the shares add up to at most 10000 (100%)
+export function assertValidRoyalty(royalty: { [accountId: string]: number }) {
+    let total = 0;
+    Object.entries(royalty).forEach(([accountId, bps]) => {
+        assert(isValidAccountId(accountId), `Invalid royalty account ID: ${accountId}`);
+        assert(Number.isInteger(bps) && bps >= 0 && bps <= 10000, `Royalty for ${accountId} must be between 0 and 10000 basis points`);
+        total += bps;
+    });
+    assert(total <= 10000, "Royalty cannot exceed 10000 basis points");
+}
+
+//get the current cap on the total royalty. State saved before the cap existed is uncapped
+export function maxRoyaltyBps(contract: Contract): number {
+    return contract.maxRoyaltyBps != null ? contract.maxRoyaltyBps : 10000;
+}
+
+//check whether the contract has a default royalty for new series. An empty map means there's none
+export function hasDefaultRoyalty(contract: Contract): boolean {
+    return contract.defaultRoyalty != null && Object.keys(contract.defaultRoyalty).length > 0;
+}
+
+/*
+    scale a series' royalty down proportionally when its total is above the contract's royalty cap. The stored
+    royalty isn't changed, the reclaimed share simply ends up with the owner since they get 100% - total royalties.
+*/
+export function clampRoyalty(contract: Contract, royalty: { [accountId: string]: number }): { [accountId: string]: number } {
+    let maxBps = maxRoyaltyBps(contract);
+    let total = Object.values(royalty).reduce((partialSum, a) => partialSum + a, 0);
+    if (total <= maxBps) {
+        return royalty;
     }
+    let clamped: { [accountId: string]: number } = {};
+    Object.entries(royalty).forEach(([accountId, bps]) => {
+        clamped[accountId] = Math.floor(bps * maxBps / total);
+    });
+    return clamped;
+}
 
-    //get the owner of the token
-    let ownerId = token.owner_id;
+//split a balance between a series' royalty recipients and the owner (who gets 100% - total royalties)
+export function royaltyPayoutFor(royalty: { [accountId: string]: number }, ownerId: string, balance: bigint): { [key: string]: string } {
     //keep track of the total perpetual royalties
     let totalPerpetual = 0;
     //keep track of the payout object to send back
     let payoutObj: { [key: string]: string } = {};
-    // @ts-ignore
-    let curSeries = contract.seriesById.get(token.series_id) as Series;
-    if (curSeries == null) {
-        near.panic("no series");
-    }
-    //get the royalty object from token
-    let royalty = curSeries.royalty;
 
-    //make sure we're not paying out to too many people (GAS limits this)
-    assert(Object.keys(royalty).length <= maxLenPayout, "Market cannot payout to that many receivers");
-    
     //go through each key and value in the royalty object
     Object.entries(royalty).forEach(([key, value], index) => {
         //only insert into the payout if the key isn't the token owner (we add their payout at the end)
@@ -49,10 +100,48 @@ export function internalNftPayout({
 
     // payout to previous owner who gets 100% - total perpetual royalties
     payoutObj[ownerId] = royaltyToPayout(10000 - totalPerpetual, balance);
+    return payoutObj;
+}
+
+//compute the payout for a token given the passed in balance, shared by nft_payout and nft_transfer_payout
+function tokenPayout(contract: Contract, token: Token, balance: bigint, maxLenPayout: number): { [key: string]: string } {
+    // @ts-ignore
+    let curSeries = contract.seriesById.get(token.series_id) as Series;
+    if (curSeries == null) {
+        near.panic("no series");
+    }
+    //get the royalty object from token, scaled down to the royalty cap
+    let royalty = clampRoyalty(contract, curSeries.royalty);
+
+    //make sure we're not paying out to too many people (GAS limits this). The owner counts as a receiver too
+    let receivers = Object.keys(royalty).filter(accountId => accountId != token.owner_id).length + 1;
+    assert(maxLenPayout == null || receivers <= maxLenPayout, "Market cannot payout to that many receivers");
+
+    //the owner gets 100% - total perpetual royalties
+    return royaltyPayoutFor(royalty, token.owner_id, balance);
+}
+
+//calculates the payout for a token given the passed in balance. This is a view method
+export function internalNftPayout({
+    contract,
+    tokenId,
+    balance,
+    maxLenPayout
+}:{
+    contract: Contract, 
+    tokenId: string,
+    balance: bigint, 
+    maxLenPayout: number,
+}): { payout: {[key: string]: string }} {
+    //get the token object
+    let token = contract.tokensById.get(tokenId) as Token;
+    if (token == null) {
+        near.panic("no token");
+    }
 
     //return the payout object
     return {
-        payout: payoutObj
+        payout: tokenPayout(contract, token, balance, maxLenPayout)
     }
 }
 
@@ -94,37 +183,8 @@ export function internalNftTransferPayout({
         previousToken.approved_account_ids,
     );
 
-    //get the owner of the token
-    let ownerId = previousToken.owner_id;
-    //keep track of the total perpetual royalties
-    let totalPerpetual = 0;
-    //keep track of the payout object to send back
-    let payoutObj: { [key: string]: string } = {};
-    // @ts-ignore
-    let curSeries = contract.seriesById.get(token.series_id) as Series;
-    if (curSeries == null) {
-        near.panic("no series");
-    }
-    //get the royalty object from token
-    let royalty = curSeries.royalty;
-
-    //make sure we're not paying out to too many people (GAS limits this)
-    assert(Object.keys(royalty).length <= maxLenPayout, "Market cannot payout to that many receivers");
-    
-    //go through each key and value in the royalty object
-    Object.entries(royalty).forEach(([key, value], index) => {
-        //only insert into the payout if the key isn't the token owner (we add their payout at the end)
-        if (key != ownerId) {
-            payoutObj[key] = royaltyToPayout(value, balance);
-            totalPerpetual += value;
-        }
-    });
-
-    // payout to previous owner who gets 100% - total perpetual royalties
-    payoutObj[ownerId] = royaltyToPayout(10000 - totalPerpetual, balance);
-
-    //return the payout object
+    //the remainder after royalties goes to the previous owner
     return {
-        payout: payoutObj
+        payout: tokenPayout(contract, previousToken, balance, maxLenPayout)
     }
-}
\ No newline at end of file
+}
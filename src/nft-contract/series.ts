@@ -1,34 +1,219 @@
 import { assert, near, UnorderedSet } from "near-sdk-js";
 import { Contract, NFT_METADATA_SPEC, NFT_STANDARD_NAME } from ".";
-import { internalAddTokenToOwner, refundDeposit } from "./internal";
-import { Series, Token, TokenMetadata } from "./metadata";
+import { assertAllowedMediaUris, assertContractOwner, assertOneYocto, internalAddTokenToOwner, internalRecordAdminAction, internalRecordTokenEvent, isSeriesMintedOut, isSeriesTransferable, seriesSupplyCap, isContractOwner, isValidAccountId, nextSeriesEdition, refundDeposit, restoreOwners, tokenEdition, eventsEnabled, assertNotPaused } from "./internal";
+import { DiscountCode, MintPrecheck, MintQuote, MintWindow, RefundableMint, Series, SeriesCreateArgs, Token, TokenMetadata } from "./metadata";
+import { internalBurnAndRefundStorage } from "./nft_core";
+import { assertValidRoyalty, hasDefaultRoyalty } from "./royalty";
+import { internalEstimatedMintStorageCost, internalStorageCovers } from "./storage";
 
-export function internalMint({
+//permission bits that a series owner can grant to managers of the series
+export const MANAGER_PERM_MINT = 1;
+export const MANAGER_PERM_EDIT_METADATA = 2;
+export const MANAGER_PERM_EDIT_ROYALTY = 4;
+export const MANAGER_PERM_MANAGE_ALLOWLIST = 8;
+
+//maximum number of accounts that can be added to or removed from a series allowlist in a single call
+const MAX_ALLOWLIST_BATCH = 100;
+
+//maximum number of hashes in a merkle allowlist proof (enough for over a million accounts)
+const MAX_MERKLE_PROOF_LEN = 24;
+
+//maximum number of series that can be created in a single create_series_batch call
+const MAX_SERIES_BATCH = 20;
+
+//maximum number of receivers that can be minted to in a single nft_mint_batch call
+const MAX_MINT_BATCH = 100;
+
+//maximum number of editions that can be previewed in a single next_claimable_editions call
+const MAX_CLAIMABLE_EDITIONS_QUERY = 100;
+
+//share (in basis points) of the price kept from a refunded mint, paid out like the proceeds of a sale
+const REFUND_FEE_BPS = 250;
+
+//maximum number of mints a single discount code can be set up for
+const MAX_DISCOUNT_CODE_USES = 10000;
+
+//limits on the discovery tags a series can have
+const MAX_SERIES_TAGS = 10;
+const MAX_TAG_LEN = 32;
+
+//check whether the passed in account is the series owner or a manager of the series with the given permission
+export function internalHasSeriesPermission(series: Series, accountId: string, perm: number): boolean {
+    if (series.owner_id == accountId) {
+        return true;
+    }
+    let perms = series.managers != null ? series.managers[accountId] : null;
+    return perms != null && (perms & perm) == perm;
+}
+
+//get the price (in yoctoNEAR) of minting a token of the series, not counting storage
+export function seriesMintPrice(series: Series): bigint {
+    return series.price != null ? BigInt(series.price) : BigInt(0);
+}
+
+//get the price of a mint after a discount code of the series is applied. Panics if the code doesn't exist or
+//has no uses left
+export function internalDiscountedPrice(contract: Contract, id: number, series: Series, code: string, price: bigint): bigint {
+    let discount = contract.discountCodes.get(discountCodeKey(id, series, code)) as DiscountCode;
+    assert(discount != null, "Invalid discount code");
+    assert(discount.uses_left > 0, "Discount code exhausted");
+    return price - price * BigInt(discount.percent_off) / BigInt(100);
+}
+
+//use up one redemption of a discount code of the series
+function internalRedeemDiscountCode(contract: Contract, id: number, series: Series, code: string) {
+    let key = discountCodeKey(id, series, code);
+    let discount = contract.discountCodes.get(key) as DiscountCode;
+    discount.uses_left -= 1;
+    //exhausted codes are kept around so redeeming them again says so instead of calling them invalid
+    contract.discountCodes.set(key, discount);
+}
+
+//discount codes are stored under the hex encoded sha256 hash of the plaintext code
+function discountCodeKey(id: number, series: Series, code: string): string {
+    return `${seriesAccountKey(id, series)}:${bytesToHex(near.sha256(code))}`;
+}
+
+/*
+    get the key a series' per-account entries (mint counts, allowlist) and discount codes are stored under. It's
+    handed out once when the series is created and never reused, so the entries follow the series through
+    update_series_id and a deleted series' entries don't carry over to a new series created with the same ID.
+    Series created before the key existed keep their entries under their ID
+*/
+function seriesAccountKey(id: number, series: Series): string {
+    return series.account_key != null ? series.account_key : `${id}`;
+}
+
+//check whether the passed in account is allowed to call nft_mint on the series
+export function internalIsAllowedMinter(contract: Contract, series: Series, accountId: string): boolean {
+    //series with a price are public drops that anyone can mint by paying
+    if (series.price != null) {
+        return true;
+    }
+    if (accountId == near.currentAccountId() || contract.approvedMinters.contains(accountId)) {
+        return true;
+    }
+    //managers with the mint permission can mint their series
+    let perms = series.managers != null ? series.managers[accountId] : null;
+    return perms != null && (perms & MANAGER_PERM_MINT) == MANAGER_PERM_MINT;
+}
+
+//get how many tokens an account has minted from a series
+function accountMintCount(contract: Contract, id: number, series: Series, accountId: string): number {
+    let count = contract.mintsPerAccount.get(`${seriesAccountKey(id, series)}:${accountId}`) as number;
+    return count != null ? count : 0;
+}
+
+//count a mint towards the account's per-account mint limit of the series
+export function internalRecordAccountMint(contract: Contract, id: number, series: Series, accountId: string) {
+    contract.mintsPerAccount.set(`${seriesAccountKey(id, series)}:${accountId}`, accountMintCount(contract, id, series, accountId) + 1);
+}
+
+//get the first reason the passed in account can't mint from the series right now, or null if nothing blocks the mint
+export function internalMintBlockReason(contract: Contract, id: number, series: Series, accountId: string, proven: boolean = false): string {
+    if (contract.paused == true) {
+        return "Contract is paused";
+    }
+    if (series.locked == true) {
+        return "Series is locked";
+    }
+    let now = near.blockTimestamp().valueOf();
+    if (series.mint_start != null && now < BigInt(series.mint_start)) {
+        return "Minting not started";
+    }
+    if (series.mint_end != null && now >= BigInt(series.mint_end)) {
+        return "Minting ended";
+    }
+    if (isSeriesMintedOut(series)) {
+        return "Series sold out";
+    }
+    if (series.presale_active == true && !proven && !contract.seriesAllowlist.contains(`${seriesAccountKey(id, series)}:${accountId}`)) {
+        return "Not on the series allowlist";
+    }
+    //series with a merkle allowlist can only be minted with a valid proof (or by accounts on the stored allowlist)
+    if (series.allowlist_merkle_root != null && !proven && !contract.seriesAllowlist.contains(`${seriesAccountKey(id, series)}:${accountId}`)) {
+        return "Not on allowlist";
+    }
+    if (series.mint_limit_per_account != null && accountMintCount(contract, id, series, accountId) >= series.mint_limit_per_account) {
+        return "Per-account mint limit reached";
+    }
+
+    return null;
+}
+
+//make sure a list of series tags is within the count and length limits
+function assertValidSeriesTags(tags?: string[]) {
+    if (tags == null) {
+        return;
+    }
+    assert(tags.length <= MAX_SERIES_TAGS, `A series can have at most ${MAX_SERIES_TAGS} tags`);
+    tags.forEach(tag => {
+        assert(tag.length > 0 && tag.length <= MAX_TAG_LEN, `Tags must be between 1 and ${MAX_TAG_LEN} characters`);
+    });
+    assert(new Set(tags).size == tags.length, "Duplicate tag");
+}
+
+//update the tag index when a series' tags change from oldTags to newTags
+function internalIndexSeriesTags(contract: Contract, id: number, oldTags?: string[], newTags?: string[]) {
+    let before = oldTags != null ? oldTags : [];
+    let after = newTags != null ? newTags : [];
+    before.filter(tag => !after.includes(tag)).forEach(tag => {
+        let ids = restoreOwners(contract.seriesByTag.get(tag));
+        if (ids == null) {
+            return;
+        }
+        ids.remove(id);
+        if (ids.isEmpty()) {
+            contract.seriesByTag.remove(tag);
+        } else {
+            contract.seriesByTag.set(tag, ids);
+        }
+    });
+    after.filter(tag => !before.includes(tag)).forEach(tag => {
+        let ids = restoreOwners(contract.seriesByTag.get(tag));
+        if (ids == null) {
+            ids = new UnorderedSet("seriesByTag" + tag);
+        }
+        ids.set(id);
+        contract.seriesByTag.set(tag, ids);
+    });
+}
+
+/*
+    remove a pseudo-random entry from a series' metadata pool and return it. The index comes from hashing the block's
+    random seed together with the edition, so the pick is deterministic for a given seed and mints sharing a seed
+    (e.g. in the same block) still land on different entries since every pick is removed from the pool.
+*/
+function takeFromMetadataPool(series: Series, edition: number): TokenMetadata {
+    let hash = near.sha256(near.randomSeed() + edition.toString());
+    let random = 0;
+    for (let i = 0; i < 4; i++) {
+        random = random * 256 + hash.charCodeAt(i);
+    }
+    let index = random % series.metadata_pool.length;
+    return series.metadata_pool.splice(index, 1)[0];
+}
+
+//mint the next token of a series to the receiver. The caller needs to have checked the minting gates already.
+export function internalMintToken({
     contract,
     id,
-    receiverId
+    series,
+    receiverId,
+    logEvent = true
 }:{
     contract: Contract,
     id: number,
-    receiverId: string
-}): void {
-    let predecessor = near.predecessorAccountId();
-    if(predecessor != near.currentAccountId()) {
-        assert(contract.approvedMinters.contains(predecessor), "Not approved minter");
-    }
-    
-    // @ts-ignore
-    let series = contract.seriesById.get(id) as Series;
-    if (series == null) {
-        near.panic("no series");
-    }
+    series: Series,
+    receiverId: string,
+    logEvent?: boolean
+}): string {
+    assertNotPaused(contract);
     let tokens = UnorderedSet.deserialize(series.tokens as UnorderedSet);
-    let curLen = tokens.len();
-    if(series.metadata.copies != null) {
-        assert(curLen < series.metadata.copies, "Series is full");
-    }
+    let edition = nextSeriesEdition(series);
+    let metadata = series.metadata_pool != null ? takeFromMetadataPool(series, edition) : undefined;
 
-    let tokenId = `${id}:${curLen + 1}`;
+    let tokenId = `${id}:${edition}`;
     tokens.set(tokenId);
     series.tokens = tokens;
     // @ts-ignore
@@ -42,15 +227,23 @@ export function internalMint({
         //we set the approved account IDs to the default value (an empty map)
         approved_account_ids: {},
         //the next approval ID is set to 0
-        next_approval_id: 0
+        next_approval_id: 0,
+        edition,
+        last_transfer_at: near.blockTimestamp().toString(),
+        metadata
     });
 
     //insert the token ID and token struct and make sure that the token doesn't exist
     assert(contract.tokensById.get(tokenId) == null, "Token already exists");
     contract.tokensById.set(tokenId, token)
+    //deployments from before the counter existed start it from the current number of tokens
+    contract.totalSupply = contract.totalSupply != null ? contract.totalSupply + 1 : contract.tokensById.len();
+    internalRecordTokenEvent(contract, tokenId, "mint");
 
     //call the internal method for adding the token to the owner
     internalAddTokenToOwner(contract, token.owner_id, tokenId)
+    //append the token to the mint log so indexers can sync incrementally
+    contract.mintLog.push(tokenId);
 
     // Construct the mint log as per the events standard.
     let nftMintLog = {
@@ -71,20 +264,422 @@ export function internalMint({
     }
     
     // Log the json.
-    near.log(`EVENT_JSON:${JSON.stringify(nftMintLog)}`);
+    if (logEvent && eventsEnabled(contract)) {
+        near.log(`EVENT_JSON:${JSON.stringify(nftMintLog)}`);
+    }
+
+    return tokenId;
+}
+
+export function internalMint({
+    contract,
+    id,
+    receiverId,
+    proof,
+    code
+}:{
+    contract: Contract,
+    id: number,
+    receiverId: string,
+    //merkle proof that the predecessor is on the series' merkle allowlist
+    proof?: string[],
+    //plaintext discount code of the series, taking a percentage off the price
+    code?: string
+}): void {
+    let predecessor = near.predecessorAccountId();
+    
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    //the first mint after the window ended closes the series instead of panicking, so the close event sticks
+    if (internalCloseEndedSeries(contract, id, series)) {
+        let deposit = near.attachedDeposit().valueOf();
+        if (deposit > BigInt(0)) {
+            const promise = near.promiseBatchCreate(predecessor);
+            near.promiseBatchActionTransfer(promise, deposit);
+        }
+        return;
+    }
+    assert(internalIsAllowedMinter(contract, series, predecessor), "Not approved minter");
+    //a proof lets the predecessor through the allowlist gates, the rest of the gates (e.g. the per-account limit) still apply
+    let proven = false;
+    if (proof != null) {
+        assert(series.allowlist_merkle_root != null && verifyMerkleProof(series.allowlist_merkle_root, predecessor, proof), "Not on allowlist");
+        proven = true;
+    }
+    //make sure none of the minting gates block the predecessor
+    let blockReason = internalMintBlockReason(contract, id, series, predecessor, proven);
+    assert(blockReason == null, blockReason);
+
+    //make sure the price is covered before anything is written
+    let price = seriesMintPrice(series);
+    if (code != null) {
+        price = internalDiscountedPrice(contract, id, series, code, price);
+        internalRedeemDiscountCode(contract, id, series, code);
+    }
+    let attachedDeposit = near.attachedDeposit().valueOf();
+    assert(attachedDeposit >= price, `Must attach at least ${price} yoctoNEAR to mint`);
+
+    //measure the initial storage being used on the contract
+    let initialStorageUsage = near.storageUsage().valueOf();
+
+    let tokenId = internalMintToken({ contract, id, series, receiverId });
+    internalRecordAccountMint(contract, id, series, predecessor);
+    //refundable series hold on to the price until the mint can't be refunded anymore
+    let holdProceeds = series.refundable == true && price > BigInt(0);
+    if (holdProceeds) {
+        let mintedAt = near.blockTimestamp().valueOf();
+        contract.refundableMints.set(tokenId, new RefundableMint({
+            seriesId: id,
+            minterId: predecessor,
+            ownerId: series.owner_id,
+            price: price.toString(),
+            mintedAt: mintedAt.toString(),
+            refundableUntil: (mintedAt + BigInt(contract.refundWindowNs)).toString()
+        }));
+    }
+
+    //the buyer pays for the storage of their token on top of the price, unless they mint to themselves and their
+    //registered storage balance still covers everything they hold
+    let requiredStorageInBytes = near.storageUsage().valueOf() - initialStorageUsage;
+    let storageCost = requiredStorageInBytes * near.storageByteCost().valueOf();
+    if (attachedDeposit < price + storageCost && receiverId == predecessor && internalStorageCovers(contract, predecessor)) {
+        storageCost = BigInt(0);
+    }
+    assert(attachedDeposit >= price + storageCost, `Must attach ${price + storageCost} yoctoNEAR to cover the price and storage`);
+
+    //send the proceeds to the series owner, minus the platform fee for the treasury
+    if (price > BigInt(0) && !holdProceeds) {
+        internalSendMintProceeds(contract, id, tokenId, series.owner_id, price);
+    }
+
+    //refund whatever was attached on top of the price and storage
+    let refund = attachedDeposit - price - storageCost;
+    if (refund > BigInt(1)) {
+        const promise = near.promiseBatchCreate(predecessor);
+        near.promiseBatchActionTransfer(promise, refund);
+    }
+}
+
+//get the share (in basis points) of paid mints that goes to the treasury, 0 while no treasury is set
+function mintPlatformFeeBps(contract: Contract): number {
+    return contract.treasuryId != null && contract.treasuryId != "" && contract.platformFeeBps != null ? contract.platformFeeBps : 0;
+}
+
+//emit series_closed the first time a mint observes that the series' minting window has ended. Returns whether it
+//was emitted, later mints are turned away with "Minting ended" as usual
+function internalCloseEndedSeries(contract: Contract, id: number, series: Series): boolean {
+    let now = near.blockTimestamp().valueOf();
+    if (contract.paused == true || series.closed_emitted == true || series.mint_end == null || now < BigInt(series.mint_end)) {
+        return false;
+    }
+    series.closed_emitted = true;
+    // @ts-ignore
+    contract.seriesById.set(id, series);
+
+    // Construct the series close log as per the events standard.
+    let seriesClosedLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The data related with the event stored in a vector.
+        event: "series_closed",
+        data: [
+            {
+                // The series that closed.
+                series_id: id,
+                // When minting ended and when that was first noticed (block timestamps in nanoseconds).
+                mint_end: series.mint_end,
+                closed_at: now.toString(),
+            }
+        ]
+    }
+
+    // Log the json.
+    near.log(`EVENT_JSON:${JSON.stringify(seriesClosedLog)}`);
+    return true;
+}
+
+//split the price of a paid mint between the treasury (platform_fee_bps of it) and the series owner
+function internalSendMintProceeds(contract: Contract, id: number, tokenId: string, ownerId: string, price: bigint) {
+    let feeBps = mintPlatformFeeBps(contract);
+    let treasuryAmount = price * BigInt(feeBps) / BigInt(10000);
+    let ownerAmount = price - treasuryAmount;
+
+    if (treasuryAmount > BigInt(0)) {
+        const promise = near.promiseBatchCreate(contract.treasuryId);
+        near.promiseBatchActionTransfer(promise, treasuryAmount);
+    }
+    if (ownerAmount > BigInt(0)) {
+        const promise = near.promiseBatchCreate(ownerId);
+        near.promiseBatchActionTransfer(promise, ownerAmount);
+    }
+
+    // Construct the mint payment log as per the events standard.
+    let mintPaymentLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The data related with the event stored in a vector.
+        event: "mint_payment",
+        data: [
+            {
+                // The series and token that were paid for.
+                series_id: id,
+                token_id: tokenId,
+                // The treasury and what it received (in yoctoNEAR).
+                treasury_id: feeBps > 0 ? contract.treasuryId : null,
+                treasury_amount: treasuryAmount.toString(),
+                // The series owner and what they received (in yoctoNEAR).
+                owner_id: ownerId,
+                owner_amount: ownerAmount.toString(),
+            }
+        ]
+    }
+
+    // Log the json.
+    near.log(`EVENT_JSON:${JSON.stringify(mintPaymentLog)}`);
+}
+
+//get the reason a held mint can't be refunded anymore, or null if its minter can still refund it
+function refundBlockReason(contract: Contract, tokenId: string, mint: RefundableMint): string {
+    let token = contract.tokensById.get(tokenId) as Token;
+    if (token == null) {
+        return "Token has been burned";
+    }
+    //auctions escrow the token through a transfer as well, so this also covers tokens being auctioned
+    if (token.owner_id != mint.minter_id || token.last_transfer_at != mint.minted_at) {
+        return "Token has been transferred";
+    }
+    if (near.blockTimestamp().valueOf() >= BigInt(mint.refundable_until)) {
+        return "Refund window has passed";
+    }
+    return null;
+}
+
+//return a paid mint of a refundable series. The token is burned, its storage is paid back and the minter is
+//refunded the price minus the refund fee, which goes out like the proceeds of a sale
+export function internalRefundMint({
+    contract,
+    tokenId
+}:{
+    contract: Contract,
+    tokenId: string
+}): void {
+    //assert that the user attached exactly 1 yoctoNEAR for security reasons
+    assertOneYocto();
+    assertNotPaused(contract);
+    let callerId = near.predecessorAccountId();
+    let mint = contract.refundableMints.get(tokenId) as RefundableMint;
+    assert(mint != null, "Mint isn't refundable");
+    assert(mint.minter_id == callerId, "Only the minter can refund the mint");
+    let blockReason = refundBlockReason(contract, tokenId, mint);
+    assert(blockReason == null, blockReason);
+
+    contract.refundableMints.remove(tokenId);
+    internalBurnAndRefundStorage(contract, tokenId, callerId);
+
+    let price = BigInt(mint.price);
+    let fee = price * BigInt(REFUND_FEE_BPS) / BigInt(10000);
+    const promise = near.promiseBatchCreate(callerId);
+    near.promiseBatchActionTransfer(promise, price - fee);
+    if (fee > BigInt(0)) {
+        internalSendMintProceeds(contract, mint.series_id, tokenId, mint.owner_id, fee);
+    }
+
+    // Construct the mint refund log as per the events standard.
+    let mintRefundLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The data related with the event stored in a vector.
+        event: "mint_refund",
+        data: [
+            {
+                // The series and token that were refunded.
+                series_id: mint.series_id,
+                token_id: tokenId,
+                // The minter and what they got back (in yoctoNEAR).
+                minter_id: callerId,
+                refund_amount: (price - fee).toString(),
+                // The part of the price that was kept (in yoctoNEAR).
+                fee: fee.toString(),
+            }
+        ]
+    }
+
+    // Log the json.
+    near.log(`EVENT_JSON:${JSON.stringify(mintRefundLog)}`);
+}
+
+//pay out the held price of a refundable mint once it can't be refunded anymore (the window passed, or the token
+//was transferred or burned). Anyone can call it
+export function internalReleaseMintProceeds({
+    contract,
+    tokenId
+}:{
+    contract: Contract,
+    tokenId: string
+}): void {
+    assertNotPaused(contract);
+    let mint = contract.refundableMints.get(tokenId) as RefundableMint;
+    assert(mint != null, "No proceeds held for the token");
+    assert(refundBlockReason(contract, tokenId, mint) != null, "Mint can still be refunded");
+
+    contract.refundableMints.remove(tokenId);
+    internalSendMintProceeds(contract, mint.series_id, tokenId, mint.owner_id, BigInt(mint.price));
+}
+
+//mint one token of a series to each of the receivers (approved minters only). The whole batch has to fit
+//within the series' supply cap, and the caller pays for the storage of every minted token.
+export function internalMintBatch({
+    contract,
+    id,
+    receiverIds
+}:{
+    contract: Contract,
+    id: number,
+    receiverIds: string[]
+}): void {
+    let predecessor = near.predecessorAccountId();
+    if(predecessor != near.currentAccountId()) {
+        assert(contract.approvedMinters.contains(predecessor), "Not approved minter");
+    }
+    assert(receiverIds.length > 0, "No receivers");
+    assert(receiverIds.length <= MAX_MINT_BATCH, `Cannot mint to more than ${MAX_MINT_BATCH} receivers at once`);
+    receiverIds.forEach(receiverId => assert(isValidAccountId(receiverId), `Invalid account ID ${receiverId}`));
+
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    assert(series.locked != true, "Series is locked");
+
+    //make sure the whole batch fits before anything is written
+    let tokens = UnorderedSet.deserialize(series.tokens as UnorderedSet);
+    let cap = seriesSupplyCap(series);
+    if (cap != null) {
+        assert(tokens.len() + receiverIds.length <= cap, `Only ${cap - tokens.len()} copies left in the series`);
+    }
+    if (series.metadata_pool != null) {
+        assert(receiverIds.length <= series.metadata_pool.length, `Only ${series.metadata_pool.length} entries left in the metadata pool`);
+    }
+
+    //measure the initial storage being used on the contract
+    let initialStorageUsage = near.storageUsage().valueOf();
+
+    //group the minted token IDs by their owner for the event log
+    let owners: string[] = [];
+    let tokenIdsByOwner: { [accountId: string]: string[] } = {};
+    for (let i = 0; i < receiverIds.length; i++) {
+        let receiverId = receiverIds[i];
+        // @ts-ignore
+        series = contract.seriesById.get(id) as Series;
+        let tokenId = internalMintToken({ contract, id, series, receiverId, logEvent: false });
+
+        if (tokenIdsByOwner[receiverId] == null) {
+            owners.push(receiverId);
+            tokenIdsByOwner[receiverId] = [];
+        }
+        tokenIdsByOwner[receiverId].push(tokenId);
+    }
+
+    // Construct the mint log as per the events standard.
+    let nftMintLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The data related with the event stored in a vector.
+        event: "nft_mint",
+        // One entry per owner.
+        data: owners.map(ownerId => {
+            return {
+                // Owner of the tokens.
+                owner_id: ownerId,
+                // Vector of token IDs that were minted.
+                token_ids: tokenIdsByOwner[ownerId],
+            }
+        })
+    }
+
+    // Log the json.
+    if (eventsEnabled(contract)) {
+        near.log(`EVENT_JSON:${JSON.stringify(nftMintLog)}`);
+    }
+
+    //calculate the required storage which was the used - initial
+    let requiredStorageInBytes = near.storageUsage().valueOf() - initialStorageUsage;
+
+    //refund any excess storage if the user attached too much. Panic if they didn't attach enough to cover the required.
+    refundDeposit(requiredStorageInBytes);
+}
+
+//validate a creator's royalty and add the platform royalty to it unless the series opted out
+function withPlatformRoyalty(contract: Contract, royalty: { [accountId: string]: number }, optOut: boolean): { [accountId: string]: number } {
+    assertValidRoyalty(royalty);
+    if (!optOut && contract.platformRoyaltyBps > 0) {
+        let platformAccount = contract.platformRoyaltyAccount;
+        let existing = royalty[platformAccount] != null ? royalty[platformAccount] : 0;
+        royalty[platformAccount] = existing + contract.platformRoyaltyBps;
+        let totalRoyalty = Object.values(royalty).reduce((partialSum, a) => partialSum + a, 0);
+        assert(totalRoyalty <= 10000, "Royalty including the platform royalty cannot exceed 10000 basis points");
+    }
+    return royalty;
 }
 
 export function internalCreateSeries({
     contract,
     id,
     metadata,
-    royalty
+    royalty,
+    optOutPlatformRoyalty,
+    ftPrice,
+    expiresAt,
+    lazy,
+    tags,
+    price,
+    mintStart,
+    mintEnd,
+    mintLimitPerAccount,
+    placeholderMetadata,
+    metadataPool,
+    transferable,
+    maxSupply,
+    refundable,
+    chargeStorage = true
 }:{
     contract: Contract,
     id: number,
     metadata: TokenMetadata,
-    royalty: { [accountId: string]: number }
+    royalty: { [accountId: string]: number },
+    optOutPlatformRoyalty?: boolean,
+    ftPrice?: [string, string],
+    expiresAt?: string,
+    lazy?: boolean,
+    tags?: string[],
+    price?: string,
+    mintStart?: string,
+    mintEnd?: string,
+    mintLimitPerAccount?: number,
+    placeholderMetadata?: TokenMetadata,
+    metadataPool?: TokenMetadata[],
+    //false creates a soulbound series whose tokens can't be transferred or approved
+    transferable?: boolean,
+    //hard cap on the number of tokens, enforced instead of metadata.copies when both are set
+    maxSupply?: number,
+    //lets minters return a paid mint with refund_mint while the refund window is open
+    refundable?: boolean,
+    //batch creation charges the storage of every series at once, so it skips the per-series refund
+    chargeStorage?: boolean
 }): void {
+    assertNotPaused(contract);
     //measure the initial storage being used on the contract TODO
     let initialStorageUsage = near.storageUsage();
 
@@ -94,14 +689,92 @@ export function internalCreateSeries({
     }
     // @ts-ignore
     assert(contract.seriesById.get(id) == null, "Series already exists");
+    assertAllowedMediaUris(contract, metadata);
+    if (placeholderMetadata != null) {
+        assertAllowedMediaUris(contract, placeholderMetadata);
+    }
+    if (metadataPool != null) {
+        assert(metadataPool.length > 0, "Metadata pool cannot be empty");
+        metadataPool.forEach(entry => assertAllowedMediaUris(contract, entry));
+    }
+    assertValidSeriesTags(tags);
+    //a series is either paid for in NEAR through nft_mint or in a fungible token through ft_transfer_call
+    assert(price == null || ftPrice == null, "A series can't have both a price and an ft_price");
+    assert(mintStart == null || mintEnd == null || BigInt(mintStart) < BigInt(mintEnd), "Minting needs to start before it ends");
+    assert(maxSupply == null || (Number.isInteger(maxSupply) && maxSupply > 0), "Max supply must be a positive integer");
+    assert(refundable != true || price != null, "Only series with a price can be refundable");
+    //lazy series derive their unminted editions, so the full supply needs to be known up front
+    assert(lazy != true || metadata.copies != null || maxSupply != null, "Lazy series need a number of copies or a max supply");
+
+    //inject the platform royalty unless the series opted out
+    let optOut = optOutPlatformRoyalty == true;
+    //series created without a royalty get a copy of the contract's default so later changes to it don't affect them
+    let creatorRoyalty = royalty != null ? royalty : Object.assign({}, hasDefaultRoyalty(contract) ? contract.defaultRoyalty : {});
+    let seriesRoyalty = withPlatformRoyalty(contract, creatorRoyalty, optOut);
+
     let series = new Series({
         metadata,
-        royalty,
+        royalty: seriesRoyalty,
         tokens: new UnorderedSet(`${id}${predecessor}`),
-        ownerId: predecessor
+        ownerId: predecessor,
+        optOutPlatformRoyalty: optOut,
+        ftPrice,
+        nextEdition: 1,
+        expiresAt,
+        managers: {},
+        transferable: transferable != false,
+        enforceRoyaltyOnTransfer: false,
+        lazy: lazy == true,
+        tags: tags != null ? tags : [],
+        price,
+        locked: false,
+        builtinMarketEnabled: true,
+        mintStart,
+        mintEnd,
+        mintLimitPerAccount,
+        placeholderMetadata,
+        //series without a placeholder show their real metadata right away
+        revealed: placeholderMetadata == null,
+        metadataPool,
+        maxSupply,
+        refundable: refundable == true,
+        accountKey: `s${contract.nextSeriesKey}`
     });
+    contract.nextSeriesKey += 1;
     // @ts-ignore
     contract.seriesById.set(id, series);
+    internalIndexSeriesTags(contract, id, [], series.tags);
+
+    // Construct the create log as per the events standard.
+    let seriesCreateLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The data related with the event stored in a vector.
+        event: "series_create",
+        data: [
+            {
+                // The series that was created.
+                series_id: id,
+                // The account that owns the series.
+                owner_id: predecessor,
+                // How many copies can be minted, null if unlimited.
+                copies: metadata.copies != null ? metadata.copies : null,
+                // The hard cap on the number of tokens, null if the copies govern.
+                max_supply: maxSupply != null ? maxSupply : null,
+                // The title of the series.
+                title: metadata.title != null ? metadata.title : null,
+            }
+        ]
+    }
+
+    // Log the json.
+    near.log(`EVENT_JSON:${JSON.stringify(seriesCreateLog)}`);
+
+    if (!chargeStorage) {
+        return;
+    }
 
     //calculate the required storage which was the used - initial TODO
     let requiredStorageInBytes = near.storageUsage().valueOf() - initialStorageUsage.valueOf();
@@ -110,6 +783,41 @@ export function internalCreateSeries({
     refundDeposit(requiredStorageInBytes);
 }
 
+//create several series in one call. Any failing series (e.g. an ID that's taken) panics the whole call so nothing
+//is written, and the attached deposit needs to cover the storage of every series together
+export function internalCreateSeriesBatch({
+    contract,
+    series
+}:{
+    contract: Contract,
+    series: SeriesCreateArgs[]
+}): number[] {
+    assert(series.length > 0, "Must create at least one series");
+    assert(series.length <= MAX_SERIES_BATCH, `Cannot create more than ${MAX_SERIES_BATCH} series at once`);
+    let ids = series.map(args => args.id);
+    assert(new Set(ids).size == ids.length, "Duplicate series ID in batch");
+
+    //measure the initial storage being used on the contract
+    let initialStorageUsage = near.storageUsage().valueOf();
+
+    series.forEach(args => internalCreateSeries({
+        contract,
+        id: args.id,
+        metadata: args.metadata,
+        royalty: args.royalty,
+        optOutPlatformRoyalty: args.opt_out_platform_royalty,
+        price: args.price,
+        transferable: args.transferable,
+        maxSupply: args.max_supply,
+        chargeStorage: false
+    }));
+
+    //the storage of every series is charged together
+    let requiredStorageInBytes = near.storageUsage().valueOf() - initialStorageUsage;
+    refundDeposit(requiredStorageInBytes);
+    return ids;
+}
+
 export function internalUpdateSeriesId({
     contract,
     currentId,
@@ -119,6 +827,7 @@ export function internalUpdateSeriesId({
     currentId: number,
     newId: number
 }): void {
+    assertNotPaused(contract);
     let caller = near.predecessorAccountId();
     // @ts-ignore
     let series = contract.seriesById.get(currentId) as Series;
@@ -128,8 +837,1188 @@ export function internalUpdateSeriesId({
     assert(series.owner_id == caller, "Not owner");
     // @ts-ignore
     assert(contract.seriesById.get(newId) == null, "New Series already exists");
+    //the per-account entries of series from before the account key existed stay where they are under the old ID
+    series.account_key = seriesAccountKey(currentId, series);
     // @ts-ignore
     contract.seriesById.remove(currentId);
     // @ts-ignore
     contract.seriesById.set(newId, series);
-}
\ No newline at end of file
+    //the tag index is keyed by series ID, so move the series over to its new ID
+    internalIndexSeriesTags(contract, currentId, series.tags, []);
+    internalIndexSeriesTags(contract, newId, [], series.tags);
+}
+
+/*
+    reconcile the series' token set with the tokens that actually exist in tokensById. Goes through a page of
+    tokensById and the same page of the series' set so big contracts can be repaired over several calls. Removing
+    an entry moves the last one of the set into its place, so run it again until nothing is removed anymore
+*/
+export function internalRepairSeriesMembership({
+    contract,
+    id,
+    fromIndex,
+    limit
+}:{
+    contract: Contract,
+    id: number,
+    fromIndex?: string,
+    limit?: number
+}): void {
+    //only the contract owner can repair series state
+    assertContractOwner(contract);
+
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    let tokens = UnorderedSet.deserialize(series.tokens as UnorderedSet);
+    let added = 0;
+    let removed = 0;
+
+    //where to start pagination - if we have a fromIndex, we'll use that - otherwise start from 0 index
+    let start = fromIndex ? parseInt(fromIndex) : 0;
+    //take the first "limit" elements in the array. If we didn't specify a limit, use 50
+    let max = limit ? limit : 50;
+
+    //add every token in the page that points at this series but is missing from the series' set
+    for (let i = start; i < contract.tokensById.len() && i < start + max; i++) {
+        let tokenId = contract.tokensById.keys.get(i) as string;
+        let token = contract.tokensById.values.get(i) as Token;
+        if (token.series_id == id && !tokens.contains(tokenId)) {
+            tokens.set(tokenId);
+            added += 1;
+        }
+    }
+
+    //remove every token in the page of the series' set that no longer exists in tokensById
+    let members: string[] = [];
+    for (let i = start; i < tokens.len() && i < start + max; i++) {
+        members.push(tokens.elements.get(i) as string);
+    }
+    for (let i = 0; i < members.length; i++) {
+        if (contract.tokensById.get(members[i]) == null) {
+            tokens.remove(members[i]);
+            removed += 1;
+        }
+    }
+
+    series.tokens = tokens;
+    // @ts-ignore
+    contract.seriesById.set(id, series);
+
+    internalRecordAdminAction(contract, `Repaired series ${id}: added ${added} tokens, removed ${removed} tokens`);
+    near.log(`Repaired series ${id}: added ${added} tokens, removed ${removed} tokens`);
+}
+
+//move every token from one series into another and delete the emptied source series (contract owner only)
+export function internalMergeSeries({
+    contract,
+    fromSeriesId,
+    intoSeriesId
+}:{
+    contract: Contract,
+    fromSeriesId: number,
+    intoSeriesId: number
+}): void {
+    assertNotPaused(contract);
+    let caller = near.predecessorAccountId();
+    assert(fromSeriesId != intoSeriesId, "Cannot merge a series into itself");
+
+    // @ts-ignore
+    let fromSeries = contract.seriesById.get(fromSeriesId) as Series;
+    // @ts-ignore
+    let intoSeries = contract.seriesById.get(intoSeriesId) as Series;
+    if (fromSeries == null || intoSeries == null) {
+        near.panic("no series");
+    }
+    //merging rewrites the editions of other people's series, so it's left to the contract owner
+    assert(isContractOwner(contract, caller), "Only the owner can merge series");
+    //locked series can't take in any more tokens
+    assert(intoSeries.locked != true, "Series is locked");
+
+    let fromTokens = UnorderedSet.deserialize(fromSeries.tokens as UnorderedSet);
+    let intoTokens = UnorderedSet.deserialize(intoSeries.tokens as UnorderedSet);
+    //make sure the target series has room for every incoming token
+    let intoCap = seriesSupplyCap(intoSeries);
+    if (intoCap != null) {
+        assert(intoTokens.len() + fromTokens.len() <= intoCap, "Series is full");
+    }
+
+    /*
+        re-parent every token while keeping its token ID. Incoming tokens are given the next sequential
+        editions of the target series (so editions never collide with the target's own tokens) and
+        remember the edition they had in the source series as their original edition.
+    */
+    let tokenIds = fromTokens.toArray();
+    for (let i = 0; i < tokenIds.length; i++) {
+        let token = contract.tokensById.get(tokenIds[i]) as Token;
+        if (token == null) {
+            continue;
+        }
+        token.original_edition = token.original_edition != null ? token.original_edition : tokenEdition(tokenIds[i], token);
+        token.edition = nextSeriesEdition(intoSeries);
+        token.series_id = intoSeriesId;
+        contract.tokensById.set(tokenIds[i], token);
+        intoTokens.set(tokenIds[i]);
+    }
+
+    intoSeries.tokens = intoTokens;
+    // @ts-ignore
+    contract.seriesById.set(intoSeriesId, intoSeries);
+
+    //clear out the source series and remove it
+    fromTokens.clear();
+    // @ts-ignore
+    contract.seriesById.remove(fromSeriesId);
+    internalIndexSeriesTags(contract, fromSeriesId, fromSeries.tags, []);
+
+    // Construct the merge log as per the events standard.
+    let seriesMergeLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The data related with the event stored in a vector.
+        event: "series_merge",
+        data: [
+            {
+                // The series that was merged and deleted.
+                from_series_id: fromSeriesId,
+                // The series that received the tokens.
+                into_series_id: intoSeriesId,
+                // Vector of token IDs that were moved.
+                token_ids: tokenIds,
+            }
+        ]
+    }
+
+    // Log the json.
+    near.log(`EVENT_JSON:${JSON.stringify(seriesMergeLog)}`);
+}
+
+//check whether the passed in account could mint from the series right now and why not if it can't
+export function internalMintPrecheck({
+    contract,
+    id,
+    accountId
+}:{
+    contract: Contract,
+    id: number,
+    accountId: string
+}): MintPrecheck {
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        return new MintPrecheck({ canMint: false, reason: "no series", requiredDeposit: "0" });
+    }
+
+    if (!internalIsAllowedMinter(contract, series, accountId)) {
+        return new MintPrecheck({ canMint: false, reason: "Not approved minter", requiredDeposit: "0" });
+    }
+
+    let blockReason = internalMintBlockReason(contract, id, series, accountId);
+    //accounts minting to themselves don't need a storage deposit while their registered balance covers one more token
+    let storageCost = internalStorageCovers(contract, accountId, 1) ? BigInt(0) : internalEstimatedMintStorageCost();
+    return new MintPrecheck({
+        canMint: blockReason == null,
+        reason: blockReason,
+        //the price of the mint plus an estimate of its storage. Anything attached beyond what's used is refunded
+        requiredDeposit: (seriesMintPrice(series) + storageCost).toString()
+    });
+}
+
+//quote what a mint of the series costs the passed in account minting to itself, itemized into the price, the
+//discount of an optional code, the platform fee taken out of the price and the storage
+export function internalEffectiveMintPrice({
+    contract,
+    id,
+    accountId,
+    code
+}:{
+    contract: Contract,
+    id: number,
+    accountId: string,
+    code?: string
+}): MintQuote {
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+
+    let basePrice = seriesMintPrice(series);
+    let price = code != null ? internalDiscountedPrice(contract, id, series, code, basePrice) : basePrice;
+    //accounts minting to themselves don't need a storage deposit while their registered balance covers one more token
+    let storageCost = internalStorageCovers(contract, accountId, 1) ? BigInt(0) : internalEstimatedMintStorageCost();
+    return new MintQuote({
+        basePrice: basePrice.toString(),
+        discount: (basePrice - price).toString(),
+        platformFee: (price * BigInt(mintPlatformFeeBps(contract)) / BigInt(10000)).toString(),
+        storageCost: storageCost.toString(),
+        requiredDeposit: (price + storageCost).toString()
+    });
+}
+
+//replace the prefix of a metadata's media and reference fields, everything else is left untouched
+function rewriteMetadataPrefix(metadata: TokenMetadata, oldPrefix: string, newPrefix: string): boolean {
+    let changed = false;
+    if (metadata.media != null && metadata.media.startsWith(oldPrefix)) {
+        metadata.media = newPrefix + metadata.media.substring(oldPrefix.length);
+        changed = true;
+    }
+    if (metadata.reference != null && metadata.reference.startsWith(oldPrefix)) {
+        metadata.reference = newPrefix + metadata.reference.substring(oldPrefix.length);
+        changed = true;
+    }
+    return changed;
+}
+
+//replace a media / reference prefix in the series metadata and emit metadata updates for a page of its tokens.
+//The series metadata (including its placeholder and metadata pool) is rewritten on the first page (from_index 0);
+//each page rewrites the metadata its tokens got from the pool or through set_token_metadata.
+export function internalRewriteMediaPrefix({
+    contract,
+    id,
+    oldPrefix,
+    newPrefix,
+    fromIndex,
+    limit
+}:{
+    contract: Contract,
+    id: number,
+    oldPrefix: string,
+    newPrefix: string,
+    fromIndex?: string,
+    limit?: number
+}): void {
+    assertNotPaused(contract);
+    let caller = near.predecessorAccountId();
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    assert(internalHasSeriesPermission(series, caller, MANAGER_PERM_EDIT_METADATA), "Not owner or metadata manager");
+
+    //where to start pagination - if we have a fromIndex, we'll use that - otherwise start from 0 index
+    let start = fromIndex ? parseInt(fromIndex) : 0;
+    //take the first "limit" elements in the array. If we didn't specify a limit, use 50
+    let max = limit ? limit : 50;
+
+    //the series metadata is only rewritten once, otherwise a new prefix that extends the old one would stack up
+    if (start == 0) {
+        rewriteMetadataPrefix(series.metadata, oldPrefix, newPrefix);
+        assertAllowedMediaUris(contract, series.metadata);
+        if (series.placeholder_metadata != null) {
+            rewriteMetadataPrefix(series.placeholder_metadata, oldPrefix, newPrefix);
+            assertAllowedMediaUris(contract, series.placeholder_metadata);
+        }
+        (series.metadata_pool != null ? series.metadata_pool : []).forEach(entry => {
+            rewriteMetadataPrefix(entry, oldPrefix, newPrefix);
+            assertAllowedMediaUris(contract, entry);
+        });
+        // @ts-ignore
+        contract.seriesById.set(id, series);
+    }
+
+    let tokens = UnorderedSet.deserialize(series.tokens as UnorderedSet);
+    let tokenIds: string[] = [];
+    for (let i = start; i < tokens.len() && i < start + max; i++) {
+        let tokenId = tokens.elements.get(i) as string;
+        tokenIds.push(tokenId);
+
+        //metadata the token was given from the pool at mint
+        let token = contract.tokensById.get(tokenId) as Token;
+        if (token != null && token.metadata != null && rewriteMetadataPrefix(token.metadata, oldPrefix, newPrefix)) {
+            assertAllowedMediaUris(contract, token.metadata);
+            contract.tokensById.set(tokenId, token);
+        }
+        //fields overridden through set_token_metadata
+        let override = contract.tokenMetadataOverrides.get(tokenId) as TokenMetadata;
+        if (override != null && rewriteMetadataPrefix(override, oldPrefix, newPrefix)) {
+            assertAllowedMediaUris(contract, override);
+            contract.tokenMetadataOverrides.set(tokenId, override);
+        }
+    }
+
+    // Construct the metadata update log as per the events standard.
+    let nftMetadataUpdateLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The data related with the event stored in a vector.
+        event: "nft_metadata_update",
+        data: [
+            {
+                // Vector of token IDs whose metadata changed.
+                token_ids: tokenIds,
+            }
+        ]
+    }
+
+    // Log the json.
+    near.log(`EVENT_JSON:${JSON.stringify(nftMetadataUpdateLog)}`);
+}
+
+//override some metadata fields of a single minted token, e.g. a unique title or image in an editions drop. Fields
+//left out fall back to the token's (or series') metadata. Passing null clears the override. Not allowed once the series is locked
+export function internalSetTokenMetadata({
+    contract,
+    tokenId,
+    metadata
+}:{
+    contract: Contract,
+    tokenId: string,
+    metadata?: TokenMetadata
+}): void {
+    assertNotPaused(contract);
+    let caller = near.predecessorAccountId();
+    let token = contract.tokensById.get(tokenId) as Token;
+    if (token == null) {
+        near.panic("no token");
+    }
+    // @ts-ignore
+    let series = contract.seriesById.get(token.series_id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    assert(internalHasSeriesPermission(series, caller, MANAGER_PERM_EDIT_METADATA), "Not owner or metadata manager");
+    assert(series.locked != true, "Series is locked");
+
+    //measure the initial storage being used on the contract
+    let initialStorageUsage = near.storageUsage().valueOf();
+
+    if (metadata == null) {
+        contract.tokenMetadataOverrides.remove(tokenId);
+    } else {
+        assertAllowedMediaUris(contract, metadata);
+        contract.tokenMetadataOverrides.set(tokenId, metadata);
+    }
+
+    //the caller pays for any storage the override adds
+    let finalStorageUsage = near.storageUsage().valueOf();
+    refundDeposit(finalStorageUsage > initialStorageUsage ? finalStorageUsage - initialStorageUsage : BigInt(0));
+
+    // Construct the metadata update log as per the events standard.
+    let nftMetadataUpdateLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The data related with the event stored in a vector.
+        event: "nft_metadata_update",
+        data: [
+            {
+                // Vector of token IDs whose metadata changed.
+                token_ids: [tokenId],
+            }
+        ]
+    }
+
+    // Log the json.
+    near.log(`EVENT_JSON:${JSON.stringify(nftMetadataUpdateLog)}`);
+}
+
+//grant a manager permissions on a series. Passing 0 as the permissions removes the manager
+export function internalSetSeriesManager({
+    contract,
+    id,
+    accountId,
+    perms
+}:{
+    contract: Contract,
+    id: number,
+    accountId: string,
+    perms: number
+}): void {
+    assertNotPaused(contract);
+    let caller = near.predecessorAccountId();
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    //only the series owner can manage who its managers are
+    assert(series.owner_id == caller, "Not owner");
+
+    let managers = series.managers != null ? series.managers : {};
+    if (perms == 0) {
+        delete managers[accountId];
+    } else {
+        managers[accountId] = perms;
+    }
+    series.managers = managers;
+    // @ts-ignore
+    contract.seriesById.set(id, series);
+}
+
+//get every manager of a series and their permission bits
+export function internalGetSeriesManagers({
+    contract,
+    id
+}:{
+    contract: Contract,
+    id: number
+}): { [accountId: string]: number } {
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null || series.managers == null) {
+        return {};
+    }
+    return series.managers;
+}
+
+//get a lazy series after making sure it exists and is lazy
+function lazySeries(contract: Contract, id: number): Series {
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    assert(series.lazy == true, "Series is not lazy");
+    return series;
+}
+
+//get how many editions of a lazy series haven't been materialized (minted) yet
+export function internalLazyAvailable({
+    contract,
+    id
+}:{
+    contract: Contract,
+    id: number
+}): string {
+    let series = lazySeries(contract, id);
+    //editions are handed out in order, so everything below the next edition has been materialized
+    let materialized = series.next_edition - 1;
+    let available = seriesSupplyCap(series) - materialized;
+    return (available > 0 ? available : 0).toString();
+}
+
+//check whether an edition of a lazy series has been materialized (minted)
+export function internalIsMaterialized({
+    contract,
+    id,
+    edition
+}:{
+    contract: Contract,
+    id: number,
+    edition: number
+}): boolean {
+    let series = lazySeries(contract, id);
+    return edition >= 1 && edition < series.next_edition;
+}
+
+//get the next n editions of a lazy series that the next mints will materialize, in the order they'll be handed out
+export function internalNextClaimableEditions({
+    contract,
+    id,
+    n
+}:{
+    contract: Contract,
+    id: number,
+    n: number
+}): number[] {
+    assert(n <= MAX_CLAIMABLE_EDITIONS_QUERY, `Cannot preview more than ${MAX_CLAIMABLE_EDITIONS_QUERY} editions at once`);
+    let series = lazySeries(contract, id);
+    let editions = [];
+    for (let edition = series.next_edition; edition <= seriesSupplyCap(series) && editions.length < n; edition++) {
+        editions.push(edition);
+    }
+    return editions;
+}
+
+//turn a series soulbound (or back to transferable). This is only allowed while nothing has been minted
+//so holders never see the transferability of their tokens change.
+export function internalSetSoulbound({
+    contract,
+    id,
+    soulbound
+}:{
+    contract: Contract,
+    id: number,
+    soulbound: boolean
+}): void {
+    assertNotPaused(contract);
+    let caller = near.predecessorAccountId();
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    assert(series.owner_id == caller, "Not owner");
+
+    let tokens = UnorderedSet.deserialize(series.tokens as UnorderedSet);
+    assert(tokens.isEmpty(), "Cannot change soulbound once tokens have been minted");
+
+    series.transferable = !soulbound;
+    // @ts-ignore
+    contract.seriesById.set(id, series);
+}
+
+//turn royalty enforcement on plain transfers on or off for a series (owner or royalty managers)
+export function internalSetEnforceRoyaltyOnTransfer({
+    contract,
+    id,
+    enforce
+}:{
+    contract: Contract,
+    id: number,
+    enforce: boolean
+}): void {
+    assertNotPaused(contract);
+    let caller = near.predecessorAccountId();
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    assert(internalHasSeriesPermission(series, caller, MANAGER_PERM_EDIT_ROYALTY), "Not owner or royalty manager");
+
+    series.enforce_royalty_on_transfer = enforce;
+    // @ts-ignore
+    contract.seriesById.set(id, series);
+}
+/*
+    move a creator's royalty share to a new account across series. Only series owned by the caller are changed,
+    unless the caller is the contract owner, which can update every series. Series whose royalty is frozen are
+    skipped. Goes through a page of all series
+    so big contracts can be processed over several calls, and returns how many series were updated.
+*/
+export function internalReassignCreatorRoyalty({
+    contract,
+    oldAccountId,
+    newAccountId,
+    fromIndex,
+    limit
+}:{
+    contract: Contract,
+    oldAccountId: string,
+    newAccountId: string,
+    fromIndex?: string,
+    limit?: number
+}): number {
+    assertNotPaused(contract);
+    let caller = near.predecessorAccountId();
+    let isOwner = isContractOwner(contract, caller);
+    assert(oldAccountId != newAccountId, "The old and new accounts should be different");
+
+    //where to start pagination - if we have a fromIndex, we'll use that - otherwise start from 0 index
+    let start = fromIndex ? parseInt(fromIndex) : 0;
+    //take the first "limit" elements in the array. If we didn't specify a limit, use 50
+    let max = limit ? limit : 50;
+
+    let updated = 0;
+    //only read the series inside the page rather than the whole map
+    for (let i = start; i < contract.seriesById.len() && i < start + max; i++) {
+        let id = contract.seriesById.keys.get(i) as number;
+        // @ts-ignore
+        let series = contract.seriesById.get(id) as Series;
+        if (series == null || series.royalty[oldAccountId] == null) {
+            continue;
+        }
+        if (!isOwner && series.owner_id != caller) {
+            continue;
+        }
+        //royalties frozen at lock can't be rewritten, not even by the contract owner
+        if (series.royalty_frozen == true) {
+            continue;
+        }
+
+        //fold the share into the new account if it already had one
+        let existing = series.royalty[newAccountId] != null ? series.royalty[newAccountId] : 0;
+        series.royalty[newAccountId] = existing + series.royalty[oldAccountId];
+        delete series.royalty[oldAccountId];
+        assertValidRoyalty(series.royalty);
+
+        // @ts-ignore
+        contract.seriesById.set(id, series);
+        updated += 1;
+    }
+    return updated;
+}
+
+//replace the discovery tags of a series. Managers with the edit metadata permission can change them as well
+export function internalSetSeriesTags({
+    contract,
+    id,
+    tags
+}:{
+    contract: Contract,
+    id: number,
+    tags: string[]
+}): void {
+    assertNotPaused(contract);
+    let caller = near.predecessorAccountId();
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    assert(internalHasSeriesPermission(series, caller, MANAGER_PERM_EDIT_METADATA), "Not owner or metadata manager");
+    assertValidSeriesTags(tags);
+
+    internalIndexSeriesTags(contract, id, series.tags, tags);
+    series.tags = tags;
+    // @ts-ignore
+    contract.seriesById.set(id, series);
+}
+
+//check whether a series has reached its supply cap so front-ends can disable minting
+export function internalSeriesIsMintedOut({
+    contract,
+    id
+}:{
+    contract: Contract,
+    id: number
+}): boolean {
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    return isSeriesMintedOut(series);
+}
+
+//get the price (in yoctoNEAR) of minting from a series, or null for series without a price
+export function internalSeriesPrice({
+    contract,
+    id
+}:{
+    contract: Contract,
+    id: number
+}): string {
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    return series.price != null ? series.price : null;
+}
+
+//add, replace or (with 0 uses) remove a discount code of a series. The code is passed in as the hex encoded sha256
+//hash of the plaintext so the plaintext never shows up on chain until it's redeemed
+export function internalSetDiscountCode({
+    contract,
+    id,
+    codeHash,
+    percentOff,
+    maxUses
+}:{
+    contract: Contract,
+    id: number,
+    codeHash: string,
+    percentOff: number,
+    maxUses: number
+}): void {
+    assertNotPaused(contract);
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    assert(series.owner_id == near.predecessorAccountId(), "Not owner");
+    assert(/^[0-9a-fA-F]{64}$/.test(codeHash), "Code hash must be 32 bytes of hex");
+    assert(Number.isInteger(maxUses) && maxUses >= 0 && maxUses <= MAX_DISCOUNT_CODE_USES, `Max uses must be between 0 and ${MAX_DISCOUNT_CODE_USES}`);
+
+    let key = `${seriesAccountKey(id, series)}:${codeHash.toLowerCase()}`;
+    if (maxUses == 0) {
+        contract.discountCodes.remove(key);
+        return;
+    }
+    assert(Number.isInteger(percentOff) && percentOff > 0 && percentOff <= 100, "Percent off must be between 1 and 100");
+    contract.discountCodes.set(key, new DiscountCode({ percentOff, usesLeft: maxUses }));
+}
+
+//delete a series that has no tokens and refund the storage it freed to the caller. The series owner and the
+//contract owner can delete a series
+export function internalDeleteSeries({
+    contract,
+    id
+}:{
+    contract: Contract,
+    id: number
+}): void {
+    assertNotPaused(contract);
+    let caller = near.predecessorAccountId();
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    assert(series.owner_id == caller || isContractOwner(contract, caller), "Not owner");
+    let tokens = UnorderedSet.deserialize(series.tokens as UnorderedSet);
+    assert(tokens.isEmpty(), "Cannot delete a series that has tokens");
+
+    //measure the storage before removing anything so the freed amount can be refunded
+    let initialStorageUsage = near.storageUsage().valueOf();
+
+    tokens.clear();
+    internalIndexSeriesTags(contract, id, series.tags, []);
+    //the per-account entries stay behind under the series' account key, which no other series is handed
+    // @ts-ignore
+    contract.seriesById.remove(id);
+
+    let freedStorageInBytes = initialStorageUsage - near.storageUsage().valueOf();
+    let refund = freedStorageInBytes * near.storageByteCost().valueOf();
+    if (refund > BigInt(0)) {
+        const promise = near.promiseBatchCreate(caller);
+        near.promiseBatchActionTransfer(promise, refund);
+    }
+
+    // Construct the delete log as per the events standard.
+    let seriesDeleteLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The data related with the event stored in a vector.
+        event: "series_delete",
+        data: [
+            {
+                // The series that was deleted.
+                series_id: id,
+                // The account that deleted it.
+                account_id: caller,
+            }
+        ]
+    }
+
+    // Log the json.
+    near.log(`EVENT_JSON:${JSON.stringify(seriesDeleteLog)}`);
+}
+
+//hand a series to another account, which then controls future mints, metadata and royalties of the series.
+//The series owner and the contract owner can transfer a series. Managers were picked by the previous owner so they're cleared
+export function internalTransferSeries({
+    contract,
+    id,
+    newOwnerId
+}:{
+    contract: Contract,
+    id: number,
+    newOwnerId: string
+}): void {
+    assertNotPaused(contract);
+    let caller = near.predecessorAccountId();
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    assert(series.owner_id == caller || isContractOwner(contract, caller), "Not owner");
+    assert(isValidAccountId(newOwnerId), "Invalid new owner account ID");
+    assert(series.owner_id != newOwnerId, "The series already belongs to the new owner");
+
+    let oldOwnerId = series.owner_id;
+    series.owner_id = newOwnerId;
+    series.managers = {};
+    // @ts-ignore
+    contract.seriesById.set(id, series);
+
+    // Construct the series transfer log as per the events standard.
+    let seriesTransferLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The data related with the event stored in a vector.
+        event: "series_transfer",
+        data: [
+            {
+                // The series that changed hands.
+                series_id: id,
+                // The previous owner of the series.
+                old_owner_id: oldOwnerId,
+                // The new owner of the series.
+                new_owner_id: newOwnerId,
+            }
+        ]
+    }
+
+    // Log the json.
+    near.log(`EVENT_JSON:${JSON.stringify(seriesTransferLog)}`);
+}
+
+//permanently stop a series from minting (and optionally from changing its royalty). There is no way to unlock a series again
+export function internalLockSeries({
+    contract,
+    id,
+    freezeRoyalty
+}:{
+    contract: Contract,
+    id: number,
+    freezeRoyalty?: boolean
+}): void {
+    assertNotPaused(contract);
+    let caller = near.predecessorAccountId();
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    assert(series.owner_id == caller, "Not owner");
+    assert(series.locked != true, "Series is already locked");
+
+    series.locked = true;
+    //optionally promise collectors the royalty will never change either
+    series.royalty_frozen = freezeRoyalty == true;
+    // @ts-ignore
+    contract.seriesById.set(id, series);
+
+    // Construct the series lock log as per the events standard.
+    let seriesLockLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The data related with the event stored in a vector.
+        event: "series_lock",
+        data: [
+            {
+                // The series that was locked.
+                series_id: id,
+                // How many tokens the series ended up with.
+                supply: UnorderedSet.deserialize(series.tokens as UnorderedSet).len(),
+                // Whether the royalty was frozen as well.
+                royalty_frozen: series.royalty_frozen,
+            }
+        ]
+    }
+
+    // Log the json.
+    near.log(`EVENT_JSON:${JSON.stringify(seriesLockLog)}`);
+}
+
+//check whether a series has been locked against minting
+export function internalSeriesIsLocked({
+    contract,
+    id
+}:{
+    contract: Contract,
+    id: number
+}): boolean {
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    return series.locked == true;
+}
+
+//check whether the tokens of a series can be transferred (false for soulbound series)
+export function internalSeriesIsTransferable({
+    contract,
+    id
+}:{
+    contract: Contract,
+    id: number
+}): boolean {
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    return isSeriesTransferable(series);
+}
+
+//get the explicit max supply of a series, null if the series has none and its copies (if any) govern
+export function internalSeriesMaxSupply({
+    contract,
+    id
+}:{
+    contract: Contract,
+    id: number
+}): number {
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    return series.max_supply != null ? series.max_supply : null;
+}
+
+//allow or block the built-in marketplace (auctions and offers) for a series' tokens. External marketplaces
+//using nft_approve keep working either way
+export function internalSetBuiltinMarketEnabled({
+    contract,
+    id,
+    enabled
+}:{
+    contract: Contract,
+    id: number,
+    enabled: boolean
+}): void {
+    assertNotPaused(contract);
+    let caller = near.predecessorAccountId();
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    assert(series.owner_id == caller, "Not owner");
+
+    series.builtin_market_enabled = enabled;
+    // @ts-ignore
+    contract.seriesById.set(id, series);
+}
+
+//get the window in which a series can be minted together with the current block timestamp
+export function internalSeriesMintWindow({
+    contract,
+    id
+}:{
+    contract: Contract,
+    id: number
+}): MintWindow {
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    return new MintWindow({
+        mintStart: series.mint_start,
+        mintEnd: series.mint_end,
+        blockTimestamp: near.blockTimestamp().toString()
+    });
+}
+
+//get how many more tokens an account can mint from a series, or null if the series has no per-account limit
+export function internalRemainingMintsForAccount({
+    contract,
+    id,
+    accountId
+}:{
+    contract: Contract,
+    id: number,
+    accountId: string
+}): number {
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    if (series.mint_limit_per_account == null) {
+        return null;
+    }
+    return Math.max(series.mint_limit_per_account - accountMintCount(contract, id, series, accountId), 0);
+}
+
+//get a series and make sure the caller can manage its allowlist (the series owner or a manager with the allowlist permission)
+function seriesForAllowlist(contract: Contract, id: number): Series {
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    assert(internalHasSeriesPermission(series, near.predecessorAccountId(), MANAGER_PERM_MANAGE_ALLOWLIST), "Not owner");
+    return series;
+}
+
+//add accounts to the allowlist of a series. Allowlisted accounts are still bound by the per-account mint limit
+export function internalAddToSeriesAllowlist({
+    contract,
+    id,
+    accountIds
+}:{
+    contract: Contract,
+    id: number,
+    accountIds: string[]
+}): void {
+    assertNotPaused(contract);
+    let series = seriesForAllowlist(contract, id);
+    assert(accountIds.length <= MAX_ALLOWLIST_BATCH, `Cannot add more than ${MAX_ALLOWLIST_BATCH} accounts at once`);
+    accountIds.forEach(accountId => contract.seriesAllowlist.set(`${seriesAccountKey(id, series)}:${accountId}`));
+}
+
+//remove accounts from the allowlist of a series
+export function internalRemoveFromSeriesAllowlist({
+    contract,
+    id,
+    accountIds
+}:{
+    contract: Contract,
+    id: number,
+    accountIds: string[]
+}): void {
+    assertNotPaused(contract);
+    let series = seriesForAllowlist(contract, id);
+    assert(accountIds.length <= MAX_ALLOWLIST_BATCH, `Cannot remove more than ${MAX_ALLOWLIST_BATCH} accounts at once`);
+    accountIds.forEach(accountId => contract.seriesAllowlist.remove(`${seriesAccountKey(id, series)}:${accountId}`));
+}
+
+//start or end the presale of a series. While it's active only allowlisted accounts can mint
+export function internalSetSeriesPresale({
+    contract,
+    id,
+    active
+}:{
+    contract: Contract,
+    id: number,
+    active: boolean
+}): void {
+    assertNotPaused(contract);
+    let series = seriesForAllowlist(contract, id);
+    series.presale_active = active;
+    // @ts-ignore
+    contract.seriesById.set(id, series);
+}
+
+//convert a hex string into a byte string
+function hexToBytes(hex: string): string {
+    assert(/^[0-9a-fA-F]{64}$/.test(hex), "Merkle hashes must be 32 bytes of hex");
+    let bytes = "";
+    for (let i = 0; i < hex.length; i += 2) {
+        bytes += String.fromCharCode(parseInt(hex.substring(i, i + 2), 16));
+    }
+    return bytes;
+}
+
+//convert a byte string into a lowercase hex string
+function bytesToHex(bytes: string): string {
+    let hex = "";
+    for (let i = 0; i < bytes.length; i++) {
+        hex += ("0" + bytes.charCodeAt(i).toString(16)).slice(-2);
+    }
+    return hex;
+}
+
+/*
+    verify a merkle proof for an account. The leaf is sha256 of the account ID bytes and each level hashes
+    sha256(min(a, b) + max(a, b)) so sibling order doesn't matter. Proof entries and the root are hex encoded
+*/
+function verifyMerkleProof(root: string, accountId: string, proof: string[]): boolean {
+    assert(proof.length <= MAX_MERKLE_PROOF_LEN, `Merkle proofs can have at most ${MAX_MERKLE_PROOF_LEN} entries`);
+    let hash = near.sha256(accountId);
+    for (let i = 0; i < proof.length; i++) {
+        let sibling = hexToBytes(proof[i]);
+        hash = hash < sibling ? near.sha256(hash + sibling) : near.sha256(sibling + hash);
+    }
+    return hash == hexToBytes(root);
+}
+
+//set (or clear, by passing null) the merkle root of a series' allowlist. While it's set, only accounts with a
+//valid proof (see nft_mint_with_proof) or on the stored allowlist can mint
+export function internalSetSeriesMerkleRoot({
+    contract,
+    id,
+    root
+}:{
+    contract: Contract,
+    id: number,
+    root?: string
+}): void {
+    assertNotPaused(contract);
+    let series = seriesForAllowlist(contract, id);
+    assert(root == null || /^[0-9a-fA-F]{64}$/.test(root), "Merkle root must be 32 bytes of hex");
+    series.allowlist_merkle_root = root;
+    // @ts-ignore
+    contract.seriesById.set(id, series);
+}
+
+//check whether an account is on the allowlist of a series
+export function internalIsOnSeriesAllowlist({
+    contract,
+    id,
+    accountId
+}:{
+    contract: Contract,
+    id: number,
+    accountId: string
+}): boolean {
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    return series != null && contract.seriesAllowlist.contains(`${seriesAccountKey(id, series)}:${accountId}`);
+}
+
+//reveal the real metadata of a series that was created with placeholder metadata. This can't be undone
+export function internalRevealSeries({
+    contract,
+    id
+}:{
+    contract: Contract,
+    id: number
+}): void {
+    assertNotPaused(contract);
+    let caller = near.predecessorAccountId();
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    assert(series.owner_id == caller, "Not owner");
+    assert(series.revealed == false, "Series is already revealed");
+
+    series.revealed = true;
+    // @ts-ignore
+    contract.seriesById.set(id, series);
+
+    // Construct the reveal log as per the events standard.
+    let seriesRevealLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The data related with the event stored in a vector.
+        event: "series_reveal",
+        data: [
+            {
+                // The series that was revealed.
+                series_id: id,
+            }
+        ]
+    }
+
+    // Log the json.
+    near.log(`EVENT_JSON:${JSON.stringify(seriesRevealLog)}`);
+}
+
+//replace the royalty of a series. The platform royalty is added again unless the series opted out of it.
+//Managers with the edit royalty permission can update it too, unless the series was locked with frozen royalties
+export function internalUpdateSeriesRoyalty({
+    contract,
+    id,
+    royalty
+}:{
+    contract: Contract,
+    id: number,
+    royalty: { [accountId: string]: number }
+}): void {
+    assertNotPaused(contract);
+    let caller = near.predecessorAccountId();
+    // @ts-ignore
+    let series = contract.seriesById.get(id) as Series;
+    if (series == null) {
+        near.panic("no series");
+    }
+    assert(internalHasSeriesPermission(series, caller, MANAGER_PERM_EDIT_ROYALTY), "Not owner");
+    assert(series.royalty_frozen != true, "Series royalty is frozen");
+
+    let oldRoyalty = series.royalty;
+    series.royalty = withPlatformRoyalty(contract, royalty, series.opt_out_platform_royalty == true);
+    // @ts-ignore
+    contract.seriesById.set(id, series);
+
+    // Construct the royalty update log as per the events standard.
+    let seriesRoyaltyUpdateLog = {
+        // Standard name ("nep171").
+        standard: NFT_STANDARD_NAME,
+        // Version of the standard ("nft-1.0.0").
+        version: NFT_METADATA_SPEC,
+        // The data related with the event stored in a vector.
+        event: "series_royalty_update",
+        data: [
+            {
+                // The series whose royalty changed.
+                series_id: id,
+                // The royalty before and after the update.
+                old_royalty: oldRoyalty,
+                new_royalty: series.royalty,
+            }
+        ]
+    }
+
+    // Log the json.
+    near.log(`EVENT_JSON:${JSON.stringify(seriesRoyaltyUpdateLog)}`);
+}
@@ -0,0 +1,155 @@
+import { assert, near } from "near-sdk-js";
+import { Contract } from ".";
+import { assertNotPaused, assertOneYocto, isValidAccountId, restoreOwners } from "./internal";
+import { StorageBalance, StorageBalanceBounds } from "./metadata";
+
+//bytes taken up by an account's entry in the storage balance map
+const ACCOUNT_REGISTRATION_BYTES = 100;
+//upper bound estimate of the storage a single held token takes up (token entry, owner set entry and series set entry)
+const STORAGE_PER_TOKEN_BYTES = 1000;
+
+//get how much is locked for an account's registration and the tokens it currently holds (plus extraTokens more)
+function storageLocked(contract: Contract, accountId: string, extraTokens: number = 0): bigint {
+    let tokens = restoreOwners(contract.tokensPerOwner.get(accountId));
+    let held = (tokens == null ? 0 : tokens.len()) + extraTokens;
+    return BigInt(ACCOUNT_REGISTRATION_BYTES + held * STORAGE_PER_TOKEN_BYTES) * near.storageByteCost().valueOf();
+}
+
+//get the storage balance of a registered account, null if the account isn't registered
+function storageBalanceOf(contract: Contract, accountId: string): StorageBalance {
+    let total = contract.storageBalances.get(accountId) as string;
+    if (total == null) {
+        return null;
+    }
+    let locked = storageLocked(contract, accountId);
+    let available = BigInt(total) > locked ? BigInt(total) - locked : BigInt(0);
+    return new StorageBalance({ total, available: available.toString() });
+}
+
+//check whether an account's registered balance covers the storage of everything it holds (plus extraTokens more).
+//Used by nft_mint to let registered accounts mint without attaching a storage deposit
+export function internalStorageCovers(contract: Contract, accountId: string, extraTokens: number = 0): boolean {
+    let total = contract.storageBalances.get(accountId) as string;
+    return total != null && BigInt(total) >= storageLocked(contract, accountId, extraTokens);
+}
+
+//upper bound estimate (in yoctoNEAR) of the storage deposit a single mint needs. Whatever isn't used is refunded
+export function internalEstimatedMintStorageCost(): bigint {
+    return BigInt(STORAGE_PER_TOKEN_BYTES) * near.storageByteCost().valueOf();
+}
+
+//the minimum balance needed to register and hold a token. There is no maximum
+export function internalStorageBalanceBounds(): StorageBalanceBounds {
+    let min = BigInt(ACCOUNT_REGISTRATION_BYTES + STORAGE_PER_TOKEN_BYTES) * near.storageByteCost().valueOf();
+    return new StorageBalanceBounds({ min: min.toString(), max: null });
+}
+
+//get the storage balance of an account
+export function internalStorageBalanceOf({
+    contract,
+    accountId
+}:{
+    contract: Contract,
+    accountId: string
+}): StorageBalance {
+    return storageBalanceOf(contract, accountId);
+}
+
+//deposit storage funds for an account (the caller if no account is passed in). With registration only, just the
+//minimum balance is kept for new accounts and the rest of the deposit is refunded
+export function internalStorageDeposit({
+    contract,
+    accountId,
+    registrationOnly
+}:{
+    contract: Contract,
+    accountId?: string,
+    registrationOnly?: boolean
+}): StorageBalance {
+    assertNotPaused(contract);
+    let predecessor = near.predecessorAccountId();
+    let account = accountId != null ? accountId : predecessor;
+    assert(isValidAccountId(account), "Invalid account ID");
+
+    let deposit = near.attachedDeposit().valueOf();
+    let total = contract.storageBalances.get(account) as string;
+    let min = BigInt(internalStorageBalanceBounds().min);
+
+    let refund = BigInt(0);
+    if (total == null) {
+        assert(deposit >= min, `Must attach at least ${min} yoctoNEAR to register`);
+        let kept = registrationOnly == true ? min : deposit;
+        refund = deposit - kept;
+        contract.storageBalances.set(account, kept.toString());
+    } else if (registrationOnly == true) {
+        //already registered, so the whole deposit goes back
+        refund = deposit;
+    } else {
+        contract.storageBalances.set(account, (BigInt(total) + deposit).toString());
+    }
+
+    if (refund > BigInt(0)) {
+        const promise = near.promiseBatchCreate(predecessor);
+        near.promiseBatchActionTransfer(promise, refund);
+    }
+
+    return storageBalanceOf(contract, account);
+}
+
+//withdraw some (or all if no amount is passed in) of the caller's available storage balance. What's locked for
+//the registration and the tokens the caller currently holds can't be withdrawn
+export function internalStorageWithdraw({
+    contract,
+    amount
+}:{
+    contract: Contract,
+    amount?: string
+}): StorageBalance {
+    //assert that the user attached exactly 1 yoctoNEAR for security reasons
+    assertOneYocto();
+    assertNotPaused(contract);
+    let predecessor = near.predecessorAccountId();
+
+    let balance = storageBalanceOf(contract, predecessor);
+    if (balance == null) {
+        near.panic("Account is not registered");
+    }
+    let toWithdraw = amount != null ? BigInt(amount) : BigInt(balance.available);
+    assert(toWithdraw <= BigInt(balance.available), `Cannot withdraw more than the available ${balance.available} yoctoNEAR`);
+
+    if (toWithdraw > BigInt(0)) {
+        contract.storageBalances.set(predecessor, (BigInt(balance.total) - toWithdraw).toString());
+        const promise = near.promiseBatchCreate(predecessor);
+        near.promiseBatchActionTransfer(promise, toWithdraw);
+    }
+
+    return storageBalanceOf(contract, predecessor);
+}
+
+//unregister the caller and refund their whole balance. Accounts that still hold tokens can't unregister, force
+//isn't supported since it would mean burning the tokens
+export function internalStorageUnregister({
+    contract,
+    force
+}:{
+    contract: Contract,
+    force?: boolean
+}): boolean {
+    //assert that the user attached exactly 1 yoctoNEAR for security reasons
+    assertOneYocto();
+    assertNotPaused(contract);
+    let predecessor = near.predecessorAccountId();
+    assert(force != true, "Force unregistering is not supported");
+
+    let total = contract.storageBalances.get(predecessor) as string;
+    if (total == null) {
+        return false;
+    }
+    let tokens = restoreOwners(contract.tokensPerOwner.get(predecessor));
+    assert(tokens == null || tokens.len() == 0, "Cannot unregister while holding tokens");
+
+    contract.storageBalances.remove(predecessor);
+    const promise = near.promiseBatchCreate(predecessor);
+    near.promiseBatchActionTransfer(promise, BigInt(total));
+    return true;
+}